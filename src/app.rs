@@ -1,30 +1,57 @@
+use std::collections::HashSet;
 use std::fs::File;
+use std::io;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use fuse_rust::Fuse;
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use sha2::{Digest, Sha256};
 
 use iced::alignment::{Horizontal, Vertical};
 use iced::border::radius;
-use iced::widget::{Column, button, column, combo_box, container, scrollable, text};
+use iced::widget::{
+    Column, button, column, combo_box, container, pick_list, row, scrollable, text, text_input,
+};
 use iced::widget::{opaque, stack};
-use iced::{Border, Element, Renderer, Task, Theme, font};
+use iced::{
+    Border, Element, Event, Renderer, Subscription, Task, Theme, clipboard, event, font, time,
+    window,
+};
 use iced::{Fill, Length};
+use iced_aw::menu::Item;
 use iced_aw::{Menu, menu_bar, menu_items};
 use iced_fonts::LUCIDE_FONT_BYTES;
-use iced_fonts::lucide::plus;
+use iced_fonts::lucide::{lock, plus};
 
 use crate::base_description_component::{
-    DescriptionElement, DescriptionImage, DescriptionSound, Link, parse_description_elements,
+    DescriptionElement, DescriptionImage, DescriptionSecret, DescriptionSound, Link,
+    parse_description_elements, plain_description, searchable_description,
     serialize_description_elements,
 };
-use crate::entity_edit_component::{InputChange, entity_edit};
-use crate::entry_component::entry;
+use crate::csv_import::{self, CsvMapping, CsvRecord, ImportMode};
+use crate::csv_import_component::{CsvImportChange, csv_import};
+use crate::custom_field::{CustomField, FieldKind, searchable_fields};
+use crate::entity_edit_component::{EntryInputs, FieldChange, InputChange, TagInputs, entity_edit};
+use crate::entry_component::{EntryActions, entry};
+use crate::folder_component::{FolderChange, folder_tree};
+use crate::generator_component::{GeneratorChange, generator};
+use crate::history_component::history;
+use crate::importers::ImportSource;
 use crate::menu_button_component::menu_button;
-use crate::search_component::search;
-use crate::theme;
-use crate::utils::{AppData, DataEntry};
-use crate::window_component::{WindowContent, WindowContentType, WindowType, custom_window};
+use crate::password_component::{PasswordChange, PasswordInputs, password_form};
+use crate::password_generator::GeneratorOptions;
+use crate::search_component::{TagFilter, TagFilterChange, TagMatch, search};
+use crate::secret_string::SecretString;
+use crate::settings::Settings;
+use crate::shamir::{self, Share};
+use crate::share_component::{ShareChange, share_export, share_recovery};
+use crate::theme::{self, APP_TITLE};
+use crate::utils::{self, AppData, Backup, DataEntry, LoadStatus, MigrationReport, unix_time};
+use crate::vault::{self, Credentials, KdfParams, LockedVault, UnlockFactors};
+use crate::window_component::{
+    PasswordPurpose, WindowContent, WindowContentType, WindowType, custom_window,
+};
 use crate::window_manager::WindowManager;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,6 +59,16 @@ pub enum InputType {
     Key,
     Description,
     Search,
+    Password,
+    PasswordConfirm,
+    PasswordCurrent,
+    KdfMemory,
+    KdfIterations,
+    ShareCount,
+    ShareThreshold,
+    ShareText,
+    Tag,
+    Folder,
 }
 
 impl std::fmt::Display for InputType {
@@ -40,6 +77,35 @@ impl std::fmt::Display for InputType {
     }
 }
 
+/// Order of the entry list, `Relevance` keeps the file order when nothing is searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Relevance,
+    Key,
+    Created,
+    Modified,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        SortOrder::Relevance,
+        SortOrder::Key,
+        SortOrder::Created,
+        SortOrder::Modified,
+    ];
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::Relevance => write!(f, "Relevance"),
+            SortOrder::Key => write!(f, "Key"),
+            SortOrder::Created => write!(f, "Newest first"),
+            SortOrder::Modified => write!(f, "Recently modified"),
+        }
+    }
+}
+
 #[derive(Clone)]
 
 pub enum OpenType {
@@ -52,17 +118,80 @@ pub enum OpenType {
 pub enum AppMessage {
     OpenWindow(WindowContent<AppMessage>),
     CloseWindow((Option<WindowContent<AppMessage>>, bool)),
-    AddEntry((DataEntry, Option<WindowContent<AppMessage>>)),
+    AddEntry(Option<WindowContent<AppMessage>>),
     InputChange(InputType, String),
     SearchChange(InputType),
+    SortChange(SortOrder),
+    TagFilterChange(TagFilterChange),
+    AddTags(String),
+    RemoveTag(String),
+    FieldChange(FieldChange),
     SaveAppData(bool),
     SaveTo(String),
-    DeleteEntry((DataEntry, bool)),
-    EditEntry(DataEntry),
+    DeleteEntry((uuid::Uuid, bool)),
+    EditEntry(uuid::Uuid),
     AddNewEntry,
     ExitApp(bool),
+    /// Saves the current file and continues with the action once it is saved.
+    SaveAndContinue(Box<AppMessage>),
+    DiscardChanges(WindowContent<AppMessage>),
     OpenFile(bool),
     FileSelected(PathBuf),
+    OpenRecent(PathBuf),
+    ExportJson(bool),
+    JsonExportSelected(PathBuf),
+    ImportJson,
+    JsonImportSelected(PathBuf),
+    ImportCsv,
+    CsvFileSelected(PathBuf),
+    CsvImportChange(CsvImportChange),
+    ImportCsvRows(WindowContent<AppMessage>),
+    OpenImport,
+    SelectImportSource(ImportSource),
+    PickImportFile(WindowContent<AppMessage>),
+    ImportFileSelected((ImportSource, PathBuf)),
+    SubmitPassword(WindowContent<AppMessage>),
+    ChangePassword,
+    SelectUnlockFactors(UnlockFactors),
+    PickKeyFile(bool),
+    KeyFileSelected((PathBuf, bool)),
+    CreateKeyFile,
+    KeyFileCreated(PathBuf),
+    Lock,
+    SetAutoLock(u32),
+    Activity,
+    Tick,
+    RevealSecret((uuid::Uuid, DescriptionSecret)),
+    CopyToClipboard(String),
+    CopyEntryKey(uuid::Uuid),
+    CopyEntryDescription(uuid::Uuid),
+    ClearClipboard,
+    SetClipboardClear(u32),
+    SetBackupCount(u32),
+    SetDefaultFile,
+    SetAutosave(u32),
+    Autosave,
+    RecoverJournal,
+    UnlockFile,
+    OpenBackups,
+    RestoreBackup((PathBuf, bool)),
+    OpenGenerator,
+    GeneratorChange(GeneratorChange),
+    InsertGenerated(WindowContent<AppMessage>),
+    ShareEntry(uuid::Uuid),
+    ShowHistory(uuid::Uuid),
+    FolderChange(FolderChange),
+    MoveEntry(uuid::Uuid),
+    MoveToFolder(WindowContent<AppMessage>),
+    RevertEntry((uuid::Uuid, usize)),
+    SplitEntry,
+    SaveShares(WindowContent<AppMessage>),
+    SharesFolderSelected((PathBuf, WindowContent<AppMessage>)),
+    RecoverFromShares,
+    PickShareFiles,
+    ShareFilesSelected(Vec<PathBuf>),
+    AddShareText,
+    CombineShares(WindowContent<AppMessage>),
     OpenLink(OpenType),
     CloseSound,
     Sound(bool),
@@ -88,30 +217,112 @@ impl AudioPlayer {
     }
 }
 
+/// How long a clicked secret stays visible before it is masked again.
+const SECRET_REVEAL_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct App {
     window_manager: WindowManager<AppMessage>,
     app_data: AppData<AppMessage>,
     theme: Theme,
     editing_id: Option<uuid::Uuid>,
-    key_input_value: String,
-    decription_input_value: String,
+    key_input_value: SecretString,
+    decription_input_value: SecretString,
+    tags_input_value: Vec<String>,
+    fields_input_value: Vec<CustomField>,
+    tag_input_value: String,
     search_input_value: String,
-    entries_sorted: Vec<DataEntry>,
+    password_input_value: SecretString,
+    password_confirm_value: SecretString,
+    password_current_value: SecretString,
+    kdf_memory_value: String,
+    kdf_iterations_value: String,
+    unlock_factors_value: UnlockFactors,
+    key_file_path: Option<PathBuf>,
+    locked_vault: Option<LockedVault>,
+    /// An opened file that becomes the current one once its vault is unlocked.
+    unlocking_path: Option<PathBuf>,
+    is_locked: bool,
+    last_activity: Instant,
+    settings: Settings,
+    revealed_secret: Option<(uuid::Uuid, DescriptionSecret, Instant)>,
+    /// Digest of the last copied value, so the clipboard is only cleared if it still holds it.
+    copied_value: Option<([u8; 32], Instant)>,
+    generator_options: GeneratorOptions,
+    generated_value: SecretString,
+    share_entry: Option<DataEntry>,
+    history_entry: Option<uuid::Uuid>,
+    share_count_value: String,
+    share_threshold_value: String,
+    share_text_value: String,
+    /// Printable text of the shares of `share_entry`, empty until they are generated.
+    exported_shares: Vec<String>,
+    recovery_shares: Vec<Share>,
+    backups: Vec<Backup>,
+    csv_records: Vec<CsvRecord>,
+    csv_mapping: CsvMapping,
+    csv_mode: ImportMode,
+    import_source: ImportSource,
+    /// Indices into `app_data.entries` in the order they are shown.
+    entries_sorted: Vec<usize>,
     search_inputs: combo_box::State<InputType>,
     searched_input: Option<InputType>,
+    sort_order: SortOrder,
+    tag_filter: Vec<String>,
+    tag_match: TagMatch,
+    /// `None` shows the entries of every folder.
+    selected_folder: Option<String>,
+    expanded_folders: HashSet<String>,
+    moving_id: Option<uuid::Uuid>,
+    folder_input_value: String,
     fuse: Fuse,
     audio_player: AudioPlayer,
+    file_path: PathBuf,
+    /// Entries changed since the file was last loaded or saved.
+    is_dirty: bool,
+    /// The recovery journal already holds the unsaved changes.
+    is_autosaved: bool,
+    /// Action waiting for a save started from the unsaved changes prompt.
+    pending_after_save: Option<AppMessage>,
 }
 
 impl App {
     pub fn new() -> (Self, Task<AppMessage>) {
         let window_manager = WindowManager::new();
         let mut app_data = AppData::new(AppMessage::None);
+        let settings = Settings::load();
+        app_data.backup_count = settings.backup_count as usize;
 
-        let load_result = app_data.load_file("data.txt".to_string());
+        // A file given on the command line wins over the one from the settings.
+        let file_path = std::env::args_os()
+            .nth(1)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| settings.default_file());
+        let load_result = app_data.load_file(file_path.to_string_lossy().to_string());
+        let mut locked_vault = None;
 
         let init_task = match load_result {
-            Ok(_) => Task::none(),
+            Ok(LoadStatus::Loaded(None)) => Task::none(),
+            Ok(LoadStatus::Loaded(Some(report))) => Task::done(Self::migration_window(
+                report,
+                AppMessage::SaveTo(file_path.to_string_lossy().to_string()),
+            )),
+            Ok(LoadStatus::Locked(vault)) => {
+                let task = if utils::has_newer_journal(&file_path) {
+                    Task::done(Self::journal_window())
+                } else {
+                    Self::unlock_flow(&vault)
+                };
+                locked_vault = Some(vault);
+                task
+            }
+            // Nothing saved yet, the first save creates the file.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                if utils::has_newer_journal(&file_path) {
+                    Task::done(Self::journal_window())
+                } else {
+                    Task::none()
+                }
+            }
             Err(error) => Task::done(AppMessage::OpenWindow(WindowContent::new(
                 WindowType::Error,
                 "Error Loading App Data.".to_string(),
@@ -141,24 +352,69 @@ impl App {
             }),
             init_task,
         ]);
-        let entries_sorted = app_data.entries.clone();
+        let entries_sorted = (0..app_data.entries.len()).collect();
+        // Until the file is unlocked there is nothing to show or save, an empty list would be
+        // saved over it.
+        let is_locked = locked_vault.is_some();
         (
             Self {
                 app_data,
                 window_manager,
                 theme: theme::default_theme(),
                 editing_id: None,
-                key_input_value: String::new(),
-                decription_input_value: String::new(),
+                key_input_value: SecretString::default(),
+                decription_input_value: SecretString::default(),
+                tags_input_value: Vec::new(),
+                fields_input_value: Vec::new(),
+                tag_input_value: String::new(),
                 search_input_value: String::new(),
+                password_input_value: SecretString::default(),
+                password_confirm_value: SecretString::default(),
+                password_current_value: SecretString::default(),
+                kdf_memory_value: String::new(),
+                kdf_iterations_value: String::new(),
+                unlock_factors_value: UnlockFactors::Password,
+                key_file_path: None,
+                locked_vault,
+                unlocking_path: None,
+                is_locked,
+                last_activity: Instant::now(),
+                settings,
+                revealed_secret: None,
+                copied_value: None,
+                generator_options: GeneratorOptions::default(),
+                generated_value: SecretString::default(),
+                share_entry: None,
+                history_entry: None,
+                share_count_value: String::new(),
+                share_threshold_value: String::new(),
+                share_text_value: String::new(),
+                exported_shares: Vec::new(),
+                recovery_shares: Vec::new(),
+                backups: Vec::new(),
+                csv_records: Vec::new(),
+                csv_mapping: CsvMapping::default(),
+                csv_mode: ImportMode::Append,
+                import_source: ImportSource::KeePassXml,
                 entries_sorted,
                 search_inputs: combo_box::State::new(vec![InputType::Key, InputType::Description]),
                 searched_input: Some(InputType::Key),
+                sort_order: SortOrder::Relevance,
+                tag_filter: Vec::new(),
+                tag_match: TagMatch::Any,
+                selected_folder: None,
+                expanded_folders: HashSet::new(),
+                moving_id: None,
+                folder_input_value: String::new(),
                 fuse: Fuse {
                     max_pattern_length: 100,
                     ..Default::default()
                 },
                 audio_player: AudioPlayer::new(),
+                file_path,
+                is_dirty: false,
+                is_autosaved: false,
+                pending_after_save: None,
             },
             combined_tasks,
         )
@@ -173,6 +429,9 @@ impl App {
             AppMessage::CloseWindow((value, is_okay)) => match value {
                 Some(window_content) => {
                     self.window_manager.remove_window_by_id(window_content.id);
+                    if !is_okay && window_content.window_type == WindowType::Password {
+                        self.pending_after_save = None;
+                    }
                     if let Some(on_okay) = window_content.on_okay
                         && is_okay
                     {
@@ -188,8 +447,11 @@ impl App {
             },
             AppMessage::AddNewEntry => {
                 self.editing_id = None;
-                self.key_input_value = String::new();
-                self.decription_input_value = String::new();
+                self.key_input_value.clear();
+                self.decription_input_value.clear();
+                self.tags_input_value.clear();
+                self.tag_input_value.clear();
+                self.fields_input_value.clear();
                 Task::done(AppMessage::OpenWindow(WindowContent::new(
                     WindowType::EntryEditor,
                     "Add Entry".to_string(),
@@ -200,11 +462,12 @@ impl App {
                     None,
                 )))
             }
-            AppMessage::DeleteEntry((entry, checked)) => {
-                if let Some(pos) = self.app_data.entries.iter().position(|x| x.id == entry.id) {
+            AppMessage::DeleteEntry((id, checked)) => {
+                if let Some(pos) = self.app_data.entries.iter().position(|x| x.id == id) {
                     if checked {
                         self.window_manager.remove_window();
                         self.app_data.entries.remove(pos);
+                        self.mark_dirty();
                         self.search_entries();
                         Task::none()
                     } else {
@@ -217,17 +480,23 @@ impl App {
                             None,
                             true,
                             true,
-                            Some(AppMessage::DeleteEntry((entry, true))),
+                            Some(AppMessage::DeleteEntry((id, true))),
                         )))
                     }
                 } else {
                     Task::none()
                 }
             }
-            AppMessage::EditEntry(entry) => {
-                self.editing_id = Some(entry.id);
-                self.key_input_value = entry.key;
-                self.decription_input_value = serialize_description_elements(entry.description);
+            AppMessage::EditEntry(id) => {
+                let Some(entry) = self.app_data.entries.iter().find(|entry| entry.id == id) else {
+                    return Task::none();
+                };
+                self.key_input_value = entry.key.clone();
+                self.decription_input_value = serialize_description_elements(&entry.description);
+                self.tags_input_value = entry.tags.clone();
+                self.fields_input_value = entry.fields.clone();
+                self.tag_input_value.clear();
+                self.editing_id = Some(id);
                 Task::done(AppMessage::OpenWindow(WindowContent::new(
                     WindowType::EntryEditor,
                     "Edit Entry".to_string(),
@@ -238,29 +507,62 @@ impl App {
                     None,
                 )))
             }
-            AppMessage::AddEntry((entry, window_content)) => {
-                if let Some(existing_entry) = self
-                    .app_data
-                    .entries
-                    .iter_mut()
-                    .find(|el| el.id == entry.id)
+            AppMessage::AddEntry(window_content) => {
+                let description = parse_description_elements(&self.decription_input_value);
+                // Tags that were typed but not added yet are kept as well.
+                let mut tags = self.tags_input_value.clone();
+                for tag in utils::split_tags(&self.tag_input_value) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                match self
+                    .editing_id
+                    .and_then(|id| self.app_data.entries.iter_mut().find(|el| el.id == id))
                 {
-                    existing_entry.key = entry.key;
-                    existing_entry.description = entry.description;
-                } else {
-                    self.app_data.entries.push(entry);
+                    Some(existing_entry) => {
+                        existing_entry.update(self.key_input_value.clone(), description);
+                        existing_entry.set_tags(tags);
+                        existing_entry.set_fields(self.fields_input_value.clone());
+                    }
+                    None => {
+                        let mut entry =
+                            DataEntry::with_description(&self.key_input_value, description);
+                        entry.tags = tags;
+                        entry.fields = self.fields_input_value.clone();
+                        // New entries go to the folder that is being looked at.
+                        entry.folder = self.selected_folder.clone().unwrap_or_default();
+                        self.app_data.entries.push(entry);
+                    }
                 }
+                self.mark_dirty();
                 self.search_entries();
                 Task::done(AppMessage::CloseWindow((window_content, true)))
             }
             AppMessage::InputChange(input_type, value) => {
                 match input_type {
-                    InputType::Key => self.key_input_value = value,
-                    InputType::Description => self.decription_input_value = value,
+                    InputType::Key => self.key_input_value.set(value),
+                    InputType::Description => self.decription_input_value.set(value),
                     InputType::Search => {
                         self.search_input_value = value;
                         self.search_entries();
                     }
+                    InputType::Password => self.password_input_value.set(value),
+                    InputType::PasswordConfirm => self.password_confirm_value.set(value),
+                    InputType::PasswordCurrent => self.password_current_value.set(value),
+                    InputType::KdfMemory => self.kdf_memory_value = value,
+                    InputType::KdfIterations => self.kdf_iterations_value = value,
+                    InputType::ShareCount => {
+                        self.share_count_value = value;
+                        self.exported_shares.clear();
+                    }
+                    InputType::ShareThreshold => {
+                        self.share_threshold_value = value;
+                        self.exported_shares.clear();
+                    }
+                    InputType::ShareText => self.share_text_value = value,
+                    InputType::Tag => self.tag_input_value = value,
+                    InputType::Folder => self.folder_input_value = value,
                 }
                 Task::none()
             }
@@ -269,14 +571,88 @@ impl App {
                 self.search_entries();
                 Task::none()
             }
+            AppMessage::SortChange(value) => {
+                self.sort_order = value;
+                self.search_entries();
+                Task::none()
+            }
+            AppMessage::TagFilterChange(change) => {
+                match change {
+                    TagFilterChange::Toggle(tag) => {
+                        match self.tag_filter.iter().position(|selected| *selected == tag) {
+                            Some(index) => {
+                                self.tag_filter.remove(index);
+                            }
+                            None => self.tag_filter.push(tag),
+                        }
+                    }
+                    TagFilterChange::Match(value) => self.tag_match = value,
+                }
+                self.search_entries();
+                Task::none()
+            }
+            AppMessage::AddTags(value) => {
+                for tag in utils::split_tags(&value) {
+                    if !self.tags_input_value.contains(&tag) {
+                        self.tags_input_value.push(tag);
+                    }
+                }
+                if value == self.tag_input_value {
+                    self.tag_input_value.clear();
+                }
+                Task::none()
+            }
+            AppMessage::RemoveTag(tag) => {
+                self.tags_input_value.retain(|existing| *existing != tag);
+                Task::none()
+            }
+            AppMessage::FieldChange(change) => {
+                let fields = &mut self.fields_input_value;
+                match change {
+                    FieldChange::Name(index, name) => {
+                        if let Some(field) = fields.get_mut(index) {
+                            field.name = name;
+                        }
+                    }
+                    FieldChange::Kind(index, kind) => {
+                        if let Some(field) = fields.get_mut(index) {
+                            field.kind = kind;
+                        }
+                    }
+                    FieldChange::Value(index, value) => {
+                        if let Some(field) = fields.get_mut(index) {
+                            field.value.set(value);
+                        }
+                    }
+                    FieldChange::Add => fields.push(CustomField {
+                        name: String::new(),
+                        kind: FieldKind::Text,
+                        value: SecretString::default(),
+                    }),
+                    FieldChange::Remove(index) => {
+                        if index < fields.len() {
+                            fields.remove(index);
+                        }
+                    }
+                }
+                Task::none()
+            }
             AppMessage::SaveAppData(save_as) => {
+                if self.is_locked {
+                    return Task::done(Self::locked_save_window());
+                }
                 if save_as {
+                    let file_name = self
+                        .file_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
                     Task::perform(
-                        async {
+                        async move {
                             rfd::AsyncFileDialog::new()
                                 .set_title("Save Your File")
                                 .add_filter("Text", &["txt"])
-                                .set_file_name("untitled.rs")
+                                .set_file_name(file_name)
                                 .save_file()
                                 .await
                                 .map(|handle| handle.path().to_path_buf())
@@ -301,29 +677,59 @@ impl App {
                         },
                     )
                 } else {
-                    Task::done(AppMessage::SaveTo("data.txt".to_string()))
+                    Task::done(AppMessage::SaveTo(self.file_path_string()))
                 }
             }
             AppMessage::SaveTo(path) => {
-                match self.app_data.save_file(path) {
-                    Ok(_) => self.window_manager.add_window(WindowContent::new(
-                        WindowType::Info,
-                        "Data saved!".to_string(),
-                        WindowContentType::StringContent("Data saved successfully.".to_string()),
-                        None,
-                        false,
+                if self.is_locked {
+                    return Task::done(Self::locked_save_window());
+                }
+                if !self.app_data.has_password() {
+                    self.password_input_value.clear();
+                    self.password_confirm_value.clear();
+                    self.unlock_factors_value = UnlockFactors::Password;
+                    return Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Password,
+                        "Set Password".to_string(),
+                        WindowContentType::PasswordContent(PasswordPurpose::Create(path)),
+                        Some(500),
                         true,
-                        None,
-                    )),
-                    Err(e) => self.window_manager.add_window(WindowContent::new(
-                        WindowType::Error,
-                        "Saving data error!".to_string(),
-                        WindowContentType::StringContent(format!("Error saving data: {}", e)),
-                        None,
-                        false,
                         true,
                         None,
-                    )),
+                    )));
+                }
+                match self.app_data.save_file(path.clone()) {
+                    Ok(_) => {
+                        self.file_path = PathBuf::from(path);
+                        self.is_dirty = false;
+                        self.remember_file();
+                        if let Some(action) = self.pending_after_save.take() {
+                            return Task::done(action);
+                        }
+                        self.window_manager.add_window(WindowContent::new(
+                            WindowType::Info,
+                            "Data saved!".to_string(),
+                            WindowContentType::StringContent(
+                                "Data saved successfully.".to_string(),
+                            ),
+                            None,
+                            false,
+                            true,
+                            None,
+                        ))
+                    }
+                    Err(e) => {
+                        self.pending_after_save = None;
+                        self.window_manager.add_window(WindowContent::new(
+                            WindowType::Error,
+                            "Saving data error!".to_string(),
+                            WindowContentType::StringContent(format!("Error saving data: {}", e)),
+                            None,
+                            false,
+                            true,
+                            None,
+                        ))
+                    }
                 }
                 Task::none()
             }
@@ -356,23 +762,324 @@ impl App {
                         },
                     )
                 } else {
-                    Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    self.unsaved_changes_guard(AppMessage::OpenFile(true))
+                }
+            }
+            AppMessage::OpenRecent(path_buf) => {
+                if !path_buf.exists() {
+                    self.settings
+                        .recent_files
+                        .retain(|recent| *recent != path_buf);
+                    let _ = self.settings.save();
+                    return Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Error loading file!".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "{} no longer exists and was removed from the recent files.",
+                            path_buf.display()
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    )));
+                }
+                self.unsaved_changes_guard(AppMessage::FileSelected(path_buf))
+            }
+            AppMessage::ExportJson(checked) => {
+                if !checked {
+                    return Task::done(AppMessage::OpenWindow(WindowContent::new(
                         WindowType::Warning,
-                        "If data not saved it will be lost!".to_string(),
+                        "Export as JSON?".to_string(),
                         WindowContentType::StringContent(
-                            "Warning if data was not saved it will be lost!".to_string(),
+                            "The exported file is not encrypted, every key and secret in it can be read by anyone with access to it."
+                                .to_string(),
                         ),
                         None,
                         true,
                         true,
-                        Some(AppMessage::OpenFile(true)),
-                    )))
+                        Some(AppMessage::ExportJson(true)),
+                    )));
+                }
+                self.window_manager.remove_window();
+                Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Export As JSON")
+                            .add_filter("JSON", &["json"])
+                            .set_file_name("export.json")
+                            .save_file()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    |path_buf: Option<PathBuf>| match path_buf {
+                        Some(path_buf) => AppMessage::JsonExportSelected(path_buf),
+                        None => AppMessage::None,
+                    },
+                )
+            }
+            AppMessage::JsonExportSelected(path_buf) => {
+                match utils::entries_to_json(&self.app_data.entries)
+                    .and_then(|json| utils::write_atomic(&path_buf, json.as_bytes()))
+                {
+                    Ok(_) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Info,
+                        "Export finished".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "{} entries were exported to {}.",
+                            self.app_data.entries.len(),
+                            path_buf.display()
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Export error".to_string(),
+                        WindowContentType::StringContent(format!("Could not export: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::ImportJson => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Import JSON")
+                        .add_filter("JSON", &["json"])
+                        .pick_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path_buf: Option<PathBuf>| match path_buf {
+                    Some(path_buf) => AppMessage::JsonImportSelected(path_buf),
+                    None => AppMessage::None,
+                },
+            ),
+            AppMessage::JsonImportSelected(path_buf) => {
+                match std::fs::read_to_string(&path_buf)
+                    .map(SecretString::from)
+                    .and_then(|json| utils::entries_from_json(&json))
+                {
+                    Ok(entries) => {
+                        let (added, replaced) = self.app_data.merge_entries(entries);
+                        self.mark_dirty();
+                        self.search_entries();
+                        Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Info,
+                            "Import finished".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "{} entries were added and {} with the same id replaced.",
+                                added, replaced
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )))
+                    }
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Import error".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "Could not import {}: {}",
+                            path_buf.display(),
+                            e
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::ImportCsv => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Import CSV")
+                        .add_filter("CSV", &["csv", "txt"])
+                        .pick_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path_buf: Option<PathBuf>| match path_buf {
+                    Some(path_buf) => AppMessage::CsvFileSelected(path_buf),
+                    None => AppMessage::None,
+                },
+            ),
+            AppMessage::CsvFileSelected(path_buf) => {
+                match std::fs::read_to_string(&path_buf).map(SecretString::from) {
+                    Ok(contents) => {
+                        self.csv_records = csv_import::parse_csv(&contents);
+                        self.csv_mapping = CsvMapping::guess(&self.csv_records);
+                        self.csv_mode = ImportMode::Append;
+                        Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::CsvImport,
+                            "Import CSV".to_string(),
+                            WindowContentType::StringContent(String::new()),
+                            Some(700),
+                            true,
+                            true,
+                            None,
+                        )))
+                    }
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Import error".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "Could not read {}: {}",
+                            path_buf.display(),
+                            e
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::CsvImportChange(change) => {
+                match change {
+                    CsvImportChange::HasHeader(value) => self.csv_mapping.has_header = value,
+                    CsvImportChange::KeyColumn(column) => self.csv_mapping.key = Some(column.index),
+                    CsvImportChange::DescriptionColumn(index, selected) => {
+                        self.csv_mapping.toggle_description(index, selected)
+                    }
+                    CsvImportChange::Mode(mode) => self.csv_mode = mode,
+                }
+                Task::none()
+            }
+            AppMessage::ImportCsvRows(window_content) => {
+                let (entries, skipped) = self.csv_mapping.map_records(&self.csv_records);
+                let (added, merged) = match self.csv_mode {
+                    ImportMode::Append => {
+                        let added = entries.len();
+                        self.app_data.entries.extend(entries);
+                        (added, 0)
+                    }
+                    ImportMode::Merge => self.app_data.merge_entries_by_key(entries),
+                };
+                if added + merged > 0 {
+                    self.mark_dirty();
+                }
+                self.csv_records.clear();
+                self.search_entries();
+                self.window_manager.remove_window_by_id(window_content.id);
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Info,
+                    "Import finished".to_string(),
+                    WindowContentType::StringContent(Self::csv_report(added, merged, &skipped)),
+                    None,
+                    false,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::OpenImport => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                WindowType::Import,
+                "Import From Password Manager".to_string(),
+                WindowContentType::StringContent(String::new()),
+                Some(500),
+                true,
+                true,
+                None,
+            ))),
+            AppMessage::SelectImportSource(source) => {
+                self.import_source = source;
+                Task::none()
+            }
+            AppMessage::PickImportFile(window_content) => {
+                self.window_manager.remove_window_by_id(window_content.id);
+                let source = self.import_source;
+                Task::perform(
+                    async move {
+                        rfd::AsyncFileDialog::new()
+                            .set_title(format!("Import {}", source))
+                            .add_filter(source.to_string(), source.extensions())
+                            .pick_file()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    move |path_buf: Option<PathBuf>| match path_buf {
+                        Some(path_buf) => AppMessage::ImportFileSelected((source, path_buf)),
+                        None => AppMessage::None,
+                    },
+                )
+            }
+            AppMessage::ImportFileSelected((source, path_buf)) => {
+                match std::fs::read_to_string(&path_buf)
+                    .map(SecretString::from)
+                    .and_then(|contents| source.import(&contents))
+                {
+                    Ok((entries, skipped)) => {
+                        let count = entries.len();
+                        self.app_data.entries.extend(entries);
+                        if count > 0 {
+                            self.mark_dirty();
+                        }
+                        self.search_entries();
+                        Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Info,
+                            "Import finished".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "{} entries were imported from the {} export.{}",
+                                count,
+                                source,
+                                Self::skipped_report(&skipped)
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )))
+                    }
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Import error".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "Could not import {}: {}",
+                            path_buf.display(),
+                            e
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
                 }
             }
             AppMessage::FileSelected(path_buf) => {
                 if let Some(path_str) = path_buf.to_str() {
                     let load_result = self.app_data.load_file(path_str.to_string());
+                    match &load_result {
+                        // The file only becomes the current one once it is unlocked.
+                        Ok(LoadStatus::Locked(_)) => {
+                            self.unlocking_path = Some(path_buf.clone());
+                            self.is_locked = true;
+                            self.is_dirty = false;
+                            self.entries_sorted.clear();
+                        }
+                        Ok(LoadStatus::Loaded(_)) => {
+                            self.file_path = path_buf.clone();
+                            self.unlocking_path = None;
+                            self.locked_vault = None;
+                            self.is_locked = false;
+                            self.is_dirty = false;
+                            self.remember_file();
+                        }
+                        Err(_) => {}
+                    }
                     let res = match load_result {
+                        Ok(LoadStatus::Locked(vault)) => {
+                            let task = Self::unlock_flow(&vault);
+                            self.locked_vault = Some(vault);
+                            self.key_file_path = None;
+                            return task;
+                        }
                         Err(e) => AppMessage::OpenWindow(WindowContent::new(
                             WindowType::Error,
                             "Error loading file!".to_string(),
@@ -385,7 +1092,11 @@ impl App {
                             true,
                             None,
                         )),
-                        Ok(_) => AppMessage::CloseWindow((None, false)),
+                        Ok(LoadStatus::Loaded(None)) => AppMessage::CloseWindow((None, false)),
+                        Ok(LoadStatus::Loaded(Some(report))) => {
+                            self.window_manager.remove_window();
+                            Self::migration_window(report, AppMessage::SaveTo(path_str.to_string()))
+                        }
                     };
                     self.search_entries();
                     Task::done(res)
@@ -403,57 +1114,576 @@ impl App {
                     )))
                 }
             }
-            AppMessage::ExitApp(value) => {
-                if value {
-                    iced::exit()
-                } else {
-                    Task::done(AppMessage::OpenWindow(WindowContent::new(
-                        WindowType::Warning,
-                        "Leaving soo soon?".to_string(),
-                        WindowContentType::StringContent(
-                            "Exit the app?\nIf you didn't save the data it will be lost."
-                                .to_string(),
-                        ),
-                        None,
-                        true,
-                        true,
-                        Some(AppMessage::ExitApp(true)),
-                    )))
-                }
-            }
-            AppMessage::OpenLink(link) => match link {
-                OpenType::OpenImage(image) => {
-                    Task::done(AppMessage::OpenWindow(WindowContent::new(
-                        WindowType::Image,
-                        "View Image".to_string(),
-                        WindowContentType::ImageContant(image),
-                        Some(600),
-                        false,
-                        true,
-                        None,
-                    )))
-                }
-                OpenType::OpenLink(link) => {
-                    if !webbrowser::open(&link.link).is_ok() {
-                        Task::done(AppMessage::OpenWindow(WindowContent::new(
-                            WindowType::Warning,
-                            "Cannot Open Link?".to_string(),
-                            WindowContentType::StringContent("Link cannot be opened.".to_string()),
+            AppMessage::SubmitPassword(window_content) => match window_content.content.clone() {
+                WindowContentType::PasswordContent(PasswordPurpose::Unlock) => {
+                    let Some(vault) = self.locked_vault.as_ref() else {
+                        self.window_manager.remove_window_by_id(window_content.id);
+                        return Task::none();
+                    };
+                    let result = self
+                        .input_credentials(vault.header.factors)
+                        .and_then(|credentials| self.app_data.unlock(vault, &credentials));
+                    self.password_input_value.clear();
+                    match result {
+                        Ok(report) => {
+                            self.locked_vault = None;
+                            self.is_locked = false;
+                            if let Some(path) = self.unlocking_path.take() {
+                                self.file_path = path;
+                                self.is_dirty = false;
+                                self.remember_file();
+                            }
+                            self.last_activity = Instant::now();
+                            self.window_manager.remove_window_by_id(window_content.id);
+                            self.search_entries();
+                            match report {
+                                Some(report) => Task::done(Self::migration_window(
+                                    report,
+                                    AppMessage::SaveAppData(false),
+                                )),
+                                None => Task::none(),
+                            }
+                        }
+                        Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Error,
+                            "Unlock failed!".to_string(),
+                            WindowContentType::StringContent(format!("{}", e)),
                             None,
                             false,
                             true,
                             None,
-                        )))
-                    } else {
-                        Task::none()
+                        ))),
                     }
                 }
-                OpenType::OpenSound(sound) => {
-                    // We use a closure to capture any error and log it
-                    let result = (|| -> Result<Sink, Box<dyn std::error::Error>> {
-                        let file = File::open(&sound.sound)?;
-
-                        // REMOVED BufReader::new() here:
+                WindowContentType::PasswordContent(PasswordPurpose::Create(path)) => {
+                    if !self.is_new_credentials_valid() {
+                        return Task::done(Self::invalid_credentials_window());
+                    }
+                    let result = self
+                        .input_credentials(self.unlock_factors_value)
+                        .and_then(|credentials| self.app_data.set_password(&credentials));
+                    self.password_input_value.clear();
+                    self.password_confirm_value.clear();
+                    self.window_manager.remove_window_by_id(window_content.id);
+                    match result {
+                        Ok(_) => Task::done(AppMessage::SaveTo(path)),
+                        Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Error,
+                            "Saving data error!".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "Error setting password: {}",
+                                e
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        ))),
+                    }
+                }
+                WindowContentType::PasswordContent(PasswordPurpose::Change(path)) => {
+                    let Some(kdf) = self.kdf_input_params() else {
+                        return Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Warning,
+                            "Invalid key derivation cost".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "Memory must be between {} and {} MiB and iterations between 1 and {}.",
+                                vault::MIN_MEMORY_MIB,
+                                vault::MAX_MEMORY_MIB,
+                                vault::MAX_ITERATIONS
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )));
+                    };
+                    if !self.is_new_credentials_valid() {
+                        return Task::done(Self::invalid_credentials_window());
+                    }
+                    let result =
+                        self.input_credentials(self.unlock_factors_value)
+                            .and_then(|credentials| {
+                                self.app_data.change_password(
+                                    path,
+                                    &self.password_current_value,
+                                    &credentials,
+                                    kdf,
+                                )
+                            });
+                    self.password_current_value.clear();
+                    match result {
+                        Ok(_) => {
+                            self.password_input_value.clear();
+                            self.password_confirm_value.clear();
+                            self.is_dirty = false;
+                            self.window_manager.remove_window_by_id(window_content.id);
+                            Task::done(AppMessage::OpenWindow(WindowContent::new(
+                                WindowType::Info,
+                                "Password changed!".to_string(),
+                                WindowContentType::StringContent(
+                                    "The file was encrypted with the new password.".to_string(),
+                                ),
+                                None,
+                                false,
+                                true,
+                                None,
+                            )))
+                        }
+                        Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Error,
+                            "Changing password failed!".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "The file was not changed: {}",
+                                e
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        ))),
+                    }
+                }
+                _ => Task::none(),
+            },
+            AppMessage::ChangePassword => match self.app_data.kdf_params() {
+                Some(kdf) => {
+                    self.password_current_value.clear();
+                    self.password_input_value.clear();
+                    self.password_confirm_value.clear();
+                    self.unlock_factors_value = self
+                        .app_data
+                        .unlock_factors()
+                        .unwrap_or(UnlockFactors::Password);
+                    self.kdf_memory_value = (kdf.memory_kib / 1024).to_string();
+                    self.kdf_iterations_value = kdf.iterations.to_string();
+                    Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Password,
+                        "Change Password".to_string(),
+                        WindowContentType::PasswordContent(PasswordPurpose::Change(
+                            self.file_path_string(),
+                        )),
+                        Some(500),
+                        true,
+                        true,
+                        None,
+                    )))
+                }
+                None => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Info,
+                    "No password set".to_string(),
+                    WindowContentType::StringContent(
+                        "This file is not encrypted yet, save it to choose a password.".to_string(),
+                    ),
+                    None,
+                    false,
+                    true,
+                    None,
+                ))),
+            },
+            AppMessage::OpenGenerator => {
+                self.generated_value = self.generator_options.generate().unwrap_or_default().into();
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Generator,
+                    "Generate Password".to_string(),
+                    WindowContentType::StringContent(String::new()),
+                    Some(500),
+                    true,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::GeneratorChange(change) => {
+                let options = &mut self.generator_options;
+                match change {
+                    GeneratorChange::Mode(mode) => options.mode = mode,
+                    GeneratorChange::Length(length) => options.length = length,
+                    GeneratorChange::Lowercase(value) => options.lowercase = value,
+                    GeneratorChange::Uppercase(value) => options.uppercase = value,
+                    GeneratorChange::Digits(value) => options.digits = value,
+                    GeneratorChange::Symbols(value) => options.symbols = value,
+                    GeneratorChange::ExcludeAmbiguous(value) => options.exclude_ambiguous = value,
+                    GeneratorChange::Words(words) => options.words = words,
+                    // Quotes and backslashes would break the secret markup the value is inserted as.
+                    GeneratorChange::Separator(value) => {
+                        options.separator = value.replace(['"', '\\'], "")
+                    }
+                    GeneratorChange::Capitalize(value) => options.capitalize = value,
+                    GeneratorChange::Regenerate => {}
+                }
+                self.generated_value = self.generator_options.generate().unwrap_or_default().into();
+                Task::none()
+            }
+            AppMessage::InsertGenerated(window_content) => {
+                if self.generated_value.is_empty() {
+                    return Task::none();
+                }
+                let secret = serialize_description_elements(&[DescriptionElement::Secret(
+                    DescriptionSecret {
+                        text: self.generator_options.mode.to_string(),
+                        secret: std::mem::take(&mut self.generated_value),
+                    },
+                )]);
+                if !self.decription_input_value.trim().is_empty() {
+                    self.decription_input_value.push_str(" ");
+                }
+                self.decription_input_value.push_str(&secret);
+                Task::done(AppMessage::CloseWindow((Some(window_content), false)))
+            }
+            AppMessage::ShareEntry(id) => {
+                self.share_entry = self.find_entry(id).cloned();
+                self.share_count_value = "5".to_string();
+                self.share_threshold_value = "3".to_string();
+                self.exported_shares.clear();
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::ShareExport,
+                    "Split Into Shares".to_string(),
+                    WindowContentType::StringContent(String::new()),
+                    Some(600),
+                    true,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::FolderChange(change) => {
+                match change {
+                    FolderChange::Select(folder) => self.selected_folder = folder,
+                    FolderChange::Toggle(folder) => {
+                        if !self.expanded_folders.remove(&folder) {
+                            self.expanded_folders.insert(folder);
+                        }
+                    }
+                }
+                self.search_entries();
+                Task::none()
+            }
+            AppMessage::MoveEntry(id) => {
+                let Some(entry) = self.find_entry(id) else {
+                    return Task::none();
+                };
+                self.folder_input_value = entry.folder.clone();
+                self.moving_id = Some(id);
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::MoveToFolder,
+                    "Move To Folder".to_string(),
+                    WindowContentType::StringContent(String::new()),
+                    Some(500),
+                    true,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::MoveToFolder(window_content) => {
+                if let Some(entry) = self.moving_id.and_then(|id| {
+                    self.app_data
+                        .entries
+                        .iter_mut()
+                        .find(|entry| entry.id == id)
+                }) {
+                    entry.set_folder(&self.folder_input_value);
+                    // Show the folder the entry went to.
+                    let mut parent = String::new();
+                    for part in entry.folder.split('/') {
+                        if !parent.is_empty() {
+                            self.expanded_folders.insert(parent.clone());
+                            parent.push('/');
+                        }
+                        parent.push_str(part);
+                    }
+                    self.mark_dirty();
+                    self.search_entries();
+                }
+                self.window_manager.remove_window_by_id(window_content.id);
+                Task::none()
+            }
+            AppMessage::ShowHistory(id) => {
+                self.history_entry = Some(id);
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::History,
+                    "Entry History".to_string(),
+                    WindowContentType::StringContent(String::new()),
+                    Some(700),
+                    true,
+                    false,
+                    None,
+                )))
+            }
+            AppMessage::RevertEntry((id, index)) => {
+                if let Some(entry) = self
+                    .app_data
+                    .entries
+                    .iter_mut()
+                    .find(|entry| entry.id == id)
+                    && entry.revert(index)
+                {
+                    self.mark_dirty();
+                    self.search_entries();
+                }
+                Task::none()
+            }
+            AppMessage::SplitEntry => match self.split_share_entry() {
+                Ok(shares) => {
+                    self.exported_shares = shares;
+                    Task::none()
+                }
+                Err(e) => Task::done(Self::share_error_window(e)),
+            },
+            AppMessage::SaveShares(window_content) => {
+                if self.exported_shares.is_empty() {
+                    match self.split_share_entry() {
+                        Ok(shares) => self.exported_shares = shares,
+                        Err(e) => return Task::done(Self::share_error_window(e)),
+                    }
+                }
+                Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Select a Folder for the Shares")
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    move |path_buf: Option<PathBuf>| match path_buf {
+                        Some(path_buf) => {
+                            AppMessage::SharesFolderSelected((path_buf, window_content.clone()))
+                        }
+                        None => AppMessage::None,
+                    },
+                )
+            }
+            AppMessage::SharesFolderSelected((folder, window_content)) => {
+                let Some(key) = self.share_entry.as_ref().map(|entry| entry.key.to_string()) else {
+                    return Task::none();
+                };
+                let file_stem: String = key
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect();
+                let count = self.exported_shares.len();
+                for (index, share) in self.exported_shares.iter().enumerate() {
+                    let path = folder.join(format!(
+                        "{}-share-{}-of-{}.txt",
+                        file_stem,
+                        index + 1,
+                        count
+                    ));
+                    if let Err(e) = std::fs::write(&path, share) {
+                        return Task::done(Self::share_error_window(e));
+                    }
+                }
+
+                self.share_entry = None;
+                self.exported_shares.clear();
+                self.window_manager.remove_window_by_id(window_content.id);
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Info,
+                    "Shares saved".to_string(),
+                    WindowContentType::StringContent(format!(
+                        "Saved {} shares to {}. Keep each one in a different place.",
+                        count,
+                        folder.display()
+                    )),
+                    None,
+                    false,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::RecoverFromShares => {
+                self.recovery_shares.clear();
+                self.share_text_value.clear();
+                Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::ShareRecovery,
+                    "Recover From Shares".to_string(),
+                    WindowContentType::StringContent(String::new()),
+                    Some(600),
+                    true,
+                    true,
+                    None,
+                )))
+            }
+            AppMessage::PickShareFiles => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select Share Files")
+                        .add_filter("Text Files", &["txt"])
+                        .pick_files()
+                        .await
+                        .map(|handles| {
+                            handles
+                                .iter()
+                                .map(|handle| handle.path().to_path_buf())
+                                .collect()
+                        })
+                },
+                |paths: Option<Vec<PathBuf>>| match paths {
+                    Some(paths) => AppMessage::ShareFilesSelected(paths),
+                    None => AppMessage::None,
+                },
+            ),
+            AppMessage::ShareFilesSelected(paths) => {
+                for path in paths {
+                    let result = std::fs::read_to_string(&path)
+                        .and_then(|contents| Share::from_text(&contents))
+                        .and_then(|share| self.add_recovery_share(share));
+                    if let Err(e) = result {
+                        return Task::done(Self::share_error_window(io::Error::new(
+                            e.kind(),
+                            format!("{}: {}", path.display(), e),
+                        )));
+                    }
+                }
+                Task::none()
+            }
+            AppMessage::AddShareText => {
+                match Share::from_text(&self.share_text_value)
+                    .and_then(|share| self.add_recovery_share(share))
+                {
+                    Ok(_) => {
+                        self.share_text_value.clear();
+                        Task::none()
+                    }
+                    Err(e) => Task::done(Self::share_error_window(e)),
+                }
+            }
+            AppMessage::CombineShares(window_content) => {
+                let result = shamir::combine(&self.recovery_shares).and_then(|secret| {
+                    String::from_utf8(secret)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                        .and_then(|text| DataEntry::from_share_text(&text))
+                });
+                match result {
+                    Ok(entry) => {
+                        let key = entry.key.to_string();
+                        self.app_data.entries.push(entry);
+                        self.mark_dirty();
+                        self.recovery_shares.clear();
+                        self.search_entries();
+                        self.window_manager.remove_window_by_id(window_content.id);
+                        Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Info,
+                            "Entry recovered".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "\"{}\" was rebuilt from its shares and added to the list.",
+                                key
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )))
+                    }
+                    Err(e) => Task::done(Self::share_error_window(e)),
+                }
+            }
+            AppMessage::SelectUnlockFactors(factors) => {
+                self.unlock_factors_value = factors;
+                Task::none()
+            }
+            AppMessage::PickKeyFile(open_unlock_window) => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Select a Key File")
+                        .pick_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                move |path_buf: Option<PathBuf>| match path_buf {
+                    Some(path_buf) => AppMessage::KeyFileSelected((path_buf, open_unlock_window)),
+                    None if open_unlock_window => Self::unlock_window(),
+                    None => AppMessage::None,
+                },
+            ),
+            AppMessage::KeyFileSelected((path_buf, open_unlock_window)) => {
+                self.key_file_path = Some(path_buf);
+                if open_unlock_window {
+                    Task::done(Self::unlock_window())
+                } else {
+                    Task::none()
+                }
+            }
+            AppMessage::CreateKeyFile => Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Create a Key File")
+                        .set_file_name("vault.key")
+                        .save_file()
+                        .await
+                        .map(|handle| handle.path().to_path_buf())
+                },
+                |path_buf: Option<PathBuf>| match path_buf {
+                    Some(path_buf) => AppMessage::KeyFileCreated(path_buf),
+                    None => AppMessage::None,
+                },
+            ),
+            AppMessage::KeyFileCreated(path_buf) => match vault::create_key_file(&path_buf) {
+                Ok(_) => {
+                    self.key_file_path = Some(path_buf);
+                    Task::none()
+                }
+                Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Error,
+                    "Key file error!".to_string(),
+                    WindowContentType::StringContent(format!("Error creating key file: {}", e)),
+                    None,
+                    false,
+                    true,
+                    None,
+                ))),
+            },
+            AppMessage::ExitApp(value) => {
+                if value {
+                    iced::exit()
+                } else {
+                    self.unsaved_changes_guard(AppMessage::ExitApp(true))
+                }
+            }
+            AppMessage::SaveAndContinue(action) => {
+                self.window_manager.remove_window();
+                self.pending_after_save = Some(*action);
+                Task::done(AppMessage::SaveAppData(false))
+            }
+            AppMessage::DiscardChanges(window_content) => {
+                self.window_manager.remove_window_by_id(window_content.id);
+                let _ = utils::remove_journal(&self.file_path);
+                match window_content.on_discard {
+                    Some(action) => Task::done(*action),
+                    None => Task::none(),
+                }
+            }
+            AppMessage::OpenLink(link) => match link {
+                OpenType::OpenImage(image) => {
+                    Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Image,
+                        "View Image".to_string(),
+                        WindowContentType::ImageContant(image),
+                        Some(600),
+                        false,
+                        true,
+                        None,
+                    )))
+                }
+                OpenType::OpenLink(link) => {
+                    if !webbrowser::open(&link.link).is_ok() {
+                        Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Warning,
+                            "Cannot Open Link?".to_string(),
+                            WindowContentType::StringContent("Link cannot be opened.".to_string()),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )))
+                    } else {
+                        Task::none()
+                    }
+                }
+                OpenType::OpenSound(sound) => {
+                    // We use a closure to capture any error and log it
+                    let result = (|| -> Result<Sink, Box<dyn std::error::Error>> {
+                        let file = File::open(&sound.sound)?;
+
+                        // REMOVED BufReader::new() here:
                         let source = Decoder::new(file)?;
 
                         let sink = Sink::try_new(&self.audio_player.stream_handle)?;
@@ -499,24 +1729,337 @@ impl App {
                         }
                     }
                 }
-            },
-            AppMessage::CloseSound => {
-                if let Some(sink) = self.audio_player.sink.take() {
-                    sink.stop(); // Replaced clear() and detach() with stop()
+            },
+            AppMessage::CloseSound => {
+                if let Some(sink) = self.audio_player.sink.take() {
+                    sink.stop(); // Replaced clear() and detach() with stop()
+                }
+                self.audio_player.is_playing = false; // Reset state
+                Task::done(AppMessage::CloseWindow((None, false)))
+            }
+            AppMessage::Sound(playing) => {
+                if let Some(sink) = &self.audio_player.sink {
+                    if playing {
+                        sink.play();
+                    } else {
+                        sink.pause();
+                    }
+                    self.audio_player.is_playing = playing; // Sync struct state
+                }
+                Task::none()
+            }
+            AppMessage::Lock => match self.app_data.lock() {
+                Ok(vault) => {
+                    self.locked_vault = Some(vault);
+                    self.is_locked = true;
+                    self.revealed_secret = None;
+                    self.entries_sorted.clear();
+                    self.editing_id = None;
+                    self.key_input_value.clear();
+                    self.decription_input_value.clear();
+                    self.tags_input_value.clear();
+                    self.tag_input_value.clear();
+                    self.fields_input_value.clear();
+                    self.password_input_value.clear();
+                    self.password_confirm_value.clear();
+                    self.password_current_value.clear();
+                    self.generated_value.clear();
+                    self.share_entry = None;
+                    self.exported_shares.clear();
+                    self.recovery_shares.clear();
+                    self.share_text_value.clear();
+                    self.csv_records.clear();
+                    self.window_manager.clear();
+                    if let Some(sink) = self.audio_player.sink.take() {
+                        sink.stop();
+                    }
+                    self.audio_player.is_playing = false;
+                    Task::batch(vec![
+                        Task::done(Self::unlock_window()),
+                        self.clear_clipboard_task(),
+                    ])
+                }
+                Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Warning,
+                    "Cannot lock".to_string(),
+                    WindowContentType::StringContent(format!(
+                        "Only an encrypted file can be locked: {}",
+                        e
+                    )),
+                    None,
+                    false,
+                    true,
+                    None,
+                ))),
+            },
+            AppMessage::SetDefaultFile => {
+                // Only a file that was loaded or unlocked is known to be the right one.
+                if self.is_locked {
+                    return Task::none();
+                }
+                self.settings.default_file =
+                    Some(std::path::absolute(&self.file_path).unwrap_or(self.file_path.clone()));
+                match self.settings.save() {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Saving settings error!".to_string(),
+                        WindowContentType::StringContent(format!("Error saving settings: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::SetAutosave(seconds) => {
+                self.settings.autosave_seconds = seconds;
+                match self.settings.save() {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Saving settings error!".to_string(),
+                        WindowContentType::StringContent(format!("Error saving settings: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::Autosave => {
+                if !self.is_dirty || self.is_autosaved || self.is_locked {
+                    return Task::none();
+                }
+                // Also set on failure, so the error is not repeated until the next change.
+                self.is_autosaved = true;
+                match self.app_data.write_journal(&self.file_path) {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Autosave failed!".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "The recovery journal was not written: {}",
+                            e
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::RecoverJournal => {
+                self.window_manager.remove_window();
+                let journal = utils::journal_path(&self.file_path);
+                match self
+                    .app_data
+                    .load_file(journal.to_string_lossy().to_string())
+                {
+                    Ok(status) => {
+                        // The changes are back in memory but still not in the file.
+                        self.is_dirty = true;
+                        self.is_autosaved = true;
+                        match status {
+                            LoadStatus::Locked(vault) => {
+                                let task = Self::unlock_flow(&vault);
+                                self.locked_vault = Some(vault);
+                                self.key_file_path = None;
+                                task
+                            }
+                            LoadStatus::Loaded(_) => {
+                                self.search_entries();
+                                Task::none()
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        // The file itself is still loaded, it is unlocked after the error is read.
+                        let error = Task::done(AppMessage::OpenWindow(WindowContent::new(
+                            WindowType::Error,
+                            "Error loading file!".to_string(),
+                            WindowContentType::StringContent(format!(
+                                "The recovery journal could not be read: {}",
+                                e
+                            )),
+                            None,
+                            false,
+                            true,
+                            None,
+                        )));
+                        match &self.locked_vault {
+                            Some(vault) => Self::unlock_flow(vault).chain(error),
+                            None => error,
+                        }
+                    }
+                }
+            }
+            AppMessage::UnlockFile => match &self.locked_vault {
+                Some(vault) => Self::unlock_flow(vault),
+                None => Task::none(),
+            },
+            AppMessage::SetBackupCount(count) => {
+                self.settings.backup_count = count;
+                self.app_data.backup_count = count as usize;
+                match self.settings.save() {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Saving settings error!".to_string(),
+                        WindowContentType::StringContent(format!("Error saving settings: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::OpenBackups => match utils::list_backups(&self.file_path) {
+                Ok(backups) => {
+                    self.backups = backups;
+                    Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Backups,
+                        "Restore Backup".to_string(),
+                        WindowContentType::StringContent(String::new()),
+                        Some(500),
+                        true,
+                        false,
+                        None,
+                    )))
+                }
+                Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                    WindowType::Error,
+                    "Backup error".to_string(),
+                    WindowContentType::StringContent(format!("Could not list backups: {}", e)),
+                    None,
+                    false,
+                    true,
+                    None,
+                ))),
+            },
+            AppMessage::RestoreBackup((backup, checked)) => {
+                if !checked {
+                    return Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Warning,
+                        "Restore backup?".to_string(),
+                        WindowContentType::StringContent(
+                            "The current file is replaced by the backup and unsaved changes are lost. The current file is kept as a backup first."
+                                .to_string(),
+                        ),
+                        None,
+                        true,
+                        true,
+                        Some(AppMessage::RestoreBackup((backup, true))),
+                    )));
+                }
+
+                let path = self.file_path.clone();
+                match utils::restore_backup(&path, &backup, self.app_data.backup_count) {
+                    Ok(_) => {
+                        self.window_manager.clear();
+                        Task::done(AppMessage::FileSelected(path))
+                    }
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Backup error".to_string(),
+                        WindowContentType::StringContent(format!(
+                            "Could not restore the backup: {}",
+                            e
+                        )),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::SetAutoLock(minutes) => {
+                self.settings.auto_lock_minutes = minutes;
+                self.last_activity = Instant::now();
+                match self.settings.save() {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Saving settings error!".to_string(),
+                        WindowContentType::StringContent(format!("Error saving settings: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
+                }
+            }
+            AppMessage::Activity => {
+                self.last_activity = Instant::now();
+                Task::none()
+            }
+            AppMessage::Tick => {
+                if let Some((_, _, revealed_at)) = &self.revealed_secret
+                    && revealed_at.elapsed() >= SECRET_REVEAL_TIMEOUT
+                {
+                    self.revealed_secret = None;
+                }
+
+                let clipboard_timeout =
+                    Duration::from_secs(u64::from(self.settings.clipboard_clear_seconds));
+                let clipboard_task = match &self.copied_value {
+                    Some((_, copied_at)) if copied_at.elapsed() >= clipboard_timeout => {
+                        self.clear_clipboard_task()
+                    }
+                    _ => Task::none(),
+                };
+
+                let timeout = Duration::from_secs(u64::from(self.settings.auto_lock_minutes) * 60);
+                if self.is_auto_lock_active() && self.last_activity.elapsed() >= timeout {
+                    Task::batch(vec![clipboard_task, Task::done(AppMessage::Lock)])
+                } else {
+                    clipboard_task
+                }
+            }
+            AppMessage::RevealSecret((entry_id, secret)) => {
+                let is_revealed = matches!(
+                    &self.revealed_secret,
+                    Some((id, revealed, _)) if *id == entry_id && *revealed == secret
+                );
+                if is_revealed {
+                    self.revealed_secret = None;
+                    Task::none()
+                } else {
+                    let value = secret.secret.to_string();
+                    self.revealed_secret = Some((entry_id, secret, Instant::now()));
+                    Task::done(AppMessage::CopyToClipboard(value))
                 }
-                self.audio_player.is_playing = false; // Reset state
-                Task::done(AppMessage::CloseWindow((None, false)))
             }
-            AppMessage::Sound(playing) => {
-                if let Some(sink) = &self.audio_player.sink {
-                    if playing {
-                        sink.play();
-                    } else {
-                        sink.pause();
-                    }
-                    self.audio_player.is_playing = playing; // Sync struct state
+            AppMessage::CopyToClipboard(value) => {
+                self.copied_value = (self.settings.clipboard_clear_seconds > 0)
+                    .then(|| (clipboard_digest(&value), Instant::now()));
+                clipboard::write(value)
+            }
+            AppMessage::CopyEntryKey(id) => match self.find_entry(id) {
+                Some(entry) => Task::done(AppMessage::CopyToClipboard(entry.key.to_string())),
+                None => Task::none(),
+            },
+            AppMessage::CopyEntryDescription(id) => match self.find_entry(id) {
+                Some(entry) => Task::done(AppMessage::CopyToClipboard(plain_description(
+                    &entry.description,
+                ))),
+                None => Task::none(),
+            },
+            AppMessage::ClearClipboard => clipboard::write(String::new()),
+            AppMessage::SetClipboardClear(seconds) => {
+                self.settings.clipboard_clear_seconds = seconds;
+                match self.settings.save() {
+                    Ok(_) => Task::none(),
+                    Err(e) => Task::done(AppMessage::OpenWindow(WindowContent::new(
+                        WindowType::Error,
+                        "Saving settings error!".to_string(),
+                        WindowContentType::StringContent(format!("Error saving settings: {}", e)),
+                        None,
+                        false,
+                        true,
+                        None,
+                    ))),
                 }
-                Task::none()
             }
             AppMessage::None => Task::none(),
         }
@@ -538,27 +2081,230 @@ impl App {
         stack(layers).into()
     }
 
+    pub fn title(&self) -> String {
+        let file_name = self
+            .file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        format!(
+            "{} - {}{}",
+            APP_TITLE,
+            file_name,
+            if self.is_dirty { " *" } else { "" }
+        )
+    }
+
     pub fn theme(&self) -> Theme {
         self.theme.clone()
     }
 
+    pub fn subscription(&self) -> Subscription<AppMessage> {
+        let mut subscriptions = Vec::new();
+
+        if self.is_auto_lock_active() {
+            subscriptions.push(event::listen_with(|event, _status, _window| match event {
+                Event::Keyboard(_) | Event::Mouse(_) | Event::Touch(_) => {
+                    Some(AppMessage::Activity)
+                }
+                _ => None,
+            }));
+        }
+        if self.is_auto_lock_active()
+            || self.revealed_secret.is_some()
+            || self.copied_value.is_some()
+            || self.has_visible_totp()
+        {
+            subscriptions.push(time::every(Duration::from_secs(1)).map(|_| AppMessage::Tick));
+        }
+        if self.settings.autosave_seconds > 0
+            && self.is_dirty
+            && !self.is_autosaved
+            && !self.is_locked
+            && self.app_data.has_password()
+        {
+            subscriptions.push(
+                time::every(Duration::from_secs(self.settings.autosave_seconds as u64))
+                    .map(|_| AppMessage::Autosave),
+            );
+        }
+        subscriptions.push(window::close_requests().map(|_| AppMessage::ExitApp(false)));
+
+        Subscription::batch(subscriptions)
+    }
+
+    /// Puts the current file at the top of the recent files. The list is only a convenience,
+    /// so failing to store it is not reported.
+    fn remember_file(&mut self) {
+        self.settings.add_recent_file(&self.file_path);
+        let _ = self.settings.save();
+    }
+
+    fn recent_file_items<'a>(&self) -> Vec<Item<'a, AppMessage, Theme, Renderer>> {
+        if self.settings.recent_files.is_empty() {
+            return vec![Item::new(menu_button(
+                text("No recent files").width(Length::Fill),
+            ))];
+        }
+        self.settings
+            .recent_files
+            .iter()
+            .map(|path| {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // The folder tells apart files with the same name.
+                let label = match path.parent().and_then(|parent| parent.file_name()) {
+                    Some(folder) => format!("{} ({})", name, folder.to_string_lossy()),
+                    None => name,
+                };
+                Item::new(
+                    menu_button(text(label).width(Length::Fill))
+                        .on_press(AppMessage::OpenRecent(path.clone())),
+                )
+            })
+            .collect()
+    }
+
+    fn mark_dirty(&mut self) {
+        self.is_dirty = true;
+        self.is_autosaved = false;
+    }
+
+    fn journal_window() -> AppMessage {
+        AppMessage::OpenWindow(
+            WindowContent::new(
+                WindowType::Warning,
+                "Recover unsaved changes?".to_string(),
+                WindowContentType::StringContent(
+                    "Autosaved changes newer than the file were found, probably from a session that did not end normally. Recover them? Discarding deletes them."
+                        .to_string(),
+                ),
+                None,
+                false,
+                true,
+                Some(AppMessage::RecoverJournal),
+            )
+            .with_okay_label("Recover")
+            .with_discard(AppMessage::UnlockFile),
+        )
+    }
+
+    fn file_path_string(&self) -> String {
+        self.file_path.to_string_lossy().to_string()
+    }
+
+    /// Runs `action` right away if nothing changed, otherwise asks to save, discard or cancel.
+    fn unsaved_changes_guard(&self, action: AppMessage) -> Task<AppMessage> {
+        if !self.is_dirty {
+            return Task::done(action);
+        }
+        // A locked vault has no key to save with, the changes can only be kept by unlocking.
+        let (message, on_save) = if self.is_locked {
+            ("There are unsaved changes, unlock to save them.", None)
+        } else {
+            (
+                "There are unsaved changes, save them first?",
+                Some(AppMessage::SaveAndContinue(Box::new(action.clone()))),
+            )
+        };
+        Task::done(AppMessage::OpenWindow(
+            WindowContent::new(
+                WindowType::Warning,
+                "Unsaved changes".to_string(),
+                WindowContentType::StringContent(message.to_string()),
+                None,
+                true,
+                on_save.is_some(),
+                on_save,
+            )
+            .with_okay_label("Save")
+            .with_discard(action),
+        ))
+    }
+
+    /// Empties the clipboard if it still holds the value copied last.
+    fn clear_clipboard_task(&mut self) -> Task<AppMessage> {
+        match self.copied_value.take() {
+            Some((digest, _)) => clipboard::read().map(move |content| match content {
+                Some(content) if clipboard_digest(&content) == digest => AppMessage::ClearClipboard,
+                _ => AppMessage::None,
+            }),
+            None => Task::none(),
+        }
+    }
+
+    /// Shown TOTP codes change over time, so the view is refreshed every second while any is.
+    fn has_visible_totp(&self) -> bool {
+        !self.is_locked
+            && self
+                .entries_sorted
+                .iter()
+                .filter_map(|&index| self.app_data.entries.get(index))
+                .any(|entry| {
+                    entry
+                        .description
+                        .iter()
+                        .any(|element| matches!(element, DescriptionElement::Totp(_)))
+                })
+    }
+
+    fn is_auto_lock_active(&self) -> bool {
+        !self.is_locked && self.settings.auto_lock_minutes > 0 && self.app_data.has_password()
+    }
+
     fn get_main_view(&self) -> Element<'_, AppMessage> {
-        let mut entries_column: Column<AppMessage, Theme, Renderer> = column![search(
-            self.search_input_value.as_str(),
-            |value| { AppMessage::InputChange(InputType::Search, value) },
-            &self.search_inputs,
-            |value: InputType| AppMessage::SearchChange(value),
-            self.searched_input.as_ref()
-        )]
+        if self.is_locked {
+            return self.get_lock_view();
+        }
+
+        let tags = self.app_data.tags();
+        let mut entries_column: Column<AppMessage, Theme, Renderer> = column![
+            row![
+                search(
+                    self.search_input_value.as_str(),
+                    |value| { AppMessage::InputChange(InputType::Search, value) },
+                    &self.search_inputs,
+                    |value: InputType| AppMessage::SearchChange(value),
+                    self.searched_input.as_ref(),
+                    TagFilter {
+                        tags: &tags,
+                        selected: &self.tag_filter,
+                        tag_match: self.tag_match,
+                    },
+                    AppMessage::TagFilterChange,
+                ),
+                pick_list(
+                    SortOrder::ALL,
+                    Some(self.sort_order),
+                    AppMessage::SortChange
+                )
+                .width(180),
+            ]
+            .spacing(10)
+        ]
         .spacing(10)
         .padding(20);
 
-        for e in &self.entries_sorted {
+        let now = unix_time();
+        for e in self
+            .entries_sorted
+            .iter()
+            .filter_map(|&index| self.app_data.entries.get(index))
+        {
             entries_column = entries_column.push(entry(
                 e,
-                AppMessage::DeleteEntry((e.clone(), false)),
-                AppMessage::EditEntry(e.clone()),
-                |value| match value {
+                EntryActions {
+                    on_delete: AppMessage::DeleteEntry((e.id, false)),
+                    on_edit: AppMessage::EditEntry(e.id),
+                    on_history: AppMessage::ShowHistory(e.id),
+                    on_move: AppMessage::MoveEntry(e.id),
+                    on_share: AppMessage::ShareEntry(e.id),
+                    on_copy_key: AppMessage::CopyEntryKey(e.id),
+                    on_copy_description: AppMessage::CopyEntryDescription(e.id),
+                },
+                move |value| match value {
                     DescriptionElement::Image(image) => {
                         AppMessage::OpenLink(OpenType::OpenImage(image))
                     }
@@ -568,8 +2314,19 @@ impl App {
                     DescriptionElement::Sound(sound) => {
                         AppMessage::OpenLink(OpenType::OpenSound(sound))
                     }
+                    DescriptionElement::Secret(secret) => AppMessage::RevealSecret((e.id, secret)),
+                    DescriptionElement::Totp(totp) => match totp.totp.code_at(unix_time()) {
+                        Some(code) => AppMessage::CopyToClipboard(code),
+                        None => AppMessage::None,
+                    },
                     DescriptionElement::Text(_) => AppMessage::None,
                 },
+                |tag| AppMessage::TagFilterChange(TagFilterChange::Toggle(tag)),
+                match &self.revealed_secret {
+                    Some((id, secret, _)) if *id == e.id => Some(secret),
+                    _ => None,
+                },
+                now,
                 &self.theme,
             ));
         }
@@ -579,13 +2336,45 @@ impl App {
             .align_x(Horizontal::Right)
             .align_y(Vertical::Bottom)
             .padding(15);
+        let folders = self.app_data.folders();
+        let entries_view: Element<AppMessage> = if folders.is_empty() {
+            scrollable(entries_column).into()
+        } else {
+            row![
+                container(scrollable(folder_tree(
+                    &folders,
+                    self.app_data.entries.len(),
+                    self.selected_folder.as_deref(),
+                    &self.expanded_folders,
+                    AppMessage::FolderChange,
+                )))
+                .width(220)
+                .padding(iced::Padding::new(20.0).right(0.0)),
+                scrollable(entries_column),
+            ]
+            .into()
+        };
         let layers: Vec<Element<AppMessage, Theme, Renderer>> = vec![
-            column![self.get_menus(), scrollable(entries_column)].into(),
+            column![self.get_menus(), entries_view].into(),
             add_button.into(),
         ];
         stack(layers).width(Fill).height(Fill).into()
     }
 
+    fn get_lock_view(&self) -> Element<'_, AppMessage> {
+        container(
+            column![
+                lock().size(48),
+                text("The file is locked.").size(20),
+                button(text("Unlock")).on_press(AppMessage::UnlockFile),
+            ]
+            .spacing(15)
+            .align_x(Horizontal::Center),
+        )
+        .center(Fill)
+        .into()
+    }
+
     fn get_menus(&self) -> Element<'_, AppMessage> {
         let menu_tpl = |items| {
             Menu::new(items)
@@ -603,14 +2392,95 @@ impl App {
                 menu_tpl(menu_items!(
                     (menu_button(text("Open").width(Length::Fill))
                         .on_press(AppMessage::OpenFile(false))),
+                    (
+                        menu_button(text("Open Recent").width(Length::Fill))
+                            .on_press(AppMessage::None),
+                        menu_tpl(self.recent_file_items())
+                    ),
                     (menu_button(text("Save").width(Length::Fill))
                         .on_press(AppMessage::SaveAppData(false))),
                     (menu_button(text("Save As").width(Length::Fill))
                         .on_press(AppMessage::SaveAppData(true))),
+                    (menu_button(text("Import JSON").width(Length::Fill))
+                        .on_press(AppMessage::ImportJson)),
+                    (menu_button(text("Export JSON").width(Length::Fill))
+                        .on_press(AppMessage::ExportJson(false))),
+                    (menu_button(text("Import CSV").width(Length::Fill))
+                        .on_press(AppMessage::ImportCsv)),
+                    (menu_button(text("Import From...").width(Length::Fill))
+                        .on_press(AppMessage::OpenImport)),
+                    (menu_button(text("Restore Backup").width(Length::Fill))
+                        .on_press(AppMessage::OpenBackups)),
+                    (menu_button(text("Recover From Shares").width(Length::Fill))
+                        .on_press(AppMessage::RecoverFromShares)),
+                    (menu_button(text("Change Password").width(Length::Fill))
+                        .on_press(AppMessage::ChangePassword)),
+                    (menu_button(text("Lock").width(Length::Fill)).on_press(AppMessage::Lock)),
                     (menu_button(text("Exit").width(Length::Fill))
                         .on_press(AppMessage::ExitApp(false))),
                 ))
             ),
+            (
+                menu_button(text("Settings")).on_press(AppMessage::None),
+                menu_tpl(menu_items!(
+                    (
+                        menu_button(text("Auto Lock").width(Length::Fill))
+                            .on_press(AppMessage::None),
+                        menu_tpl(Self::choice_items(
+                            &[0, 1, 5, 15, 30],
+                            self.settings.auto_lock_minutes,
+                            |minutes| match minutes {
+                                0 => "Off".to_string(),
+                                1 => "1 minute".to_string(),
+                                _ => format!("{} minutes", minutes),
+                            },
+                            AppMessage::SetAutoLock,
+                        ))
+                    ),
+                    (
+                        menu_button(text("Clear Clipboard").width(Length::Fill))
+                            .on_press(AppMessage::None),
+                        menu_tpl(Self::choice_items(
+                            &[0, 10, 20, 30, 60],
+                            self.settings.clipboard_clear_seconds,
+                            |seconds| match seconds {
+                                0 => "Never".to_string(),
+                                _ => format!("After {} seconds", seconds),
+                            },
+                            AppMessage::SetClipboardClear,
+                        ))
+                    ),
+                    (
+                        menu_button(text("Backups").width(Length::Fill)).on_press(AppMessage::None),
+                        menu_tpl(Self::choice_items(
+                            &[0, 3, 5, 10, 20],
+                            self.settings.backup_count,
+                            |count| match count {
+                                0 => "Off".to_string(),
+                                _ => format!("Keep {}", count),
+                            },
+                            AppMessage::SetBackupCount,
+                        ))
+                    ),
+                    (
+                        menu_button(text("Autosave").width(Length::Fill))
+                            .on_press(AppMessage::None),
+                        menu_tpl(Self::choice_items(
+                            &[0, 30, 60, 300],
+                            self.settings.autosave_seconds,
+                            |seconds| match seconds {
+                                0 => "Off".to_string(),
+                                60 => "Every minute".to_string(),
+                                _ if seconds % 60 == 0 => format!("Every {} minutes", seconds / 60),
+                                _ => format!("Every {} seconds", seconds),
+                            },
+                            AppMessage::SetAutosave,
+                        ))
+                    ),
+                    (menu_button(text("Open This File On Start").width(Length::Fill))
+                        .on_press_maybe((!self.is_locked).then_some(AppMessage::SetDefaultFile))),
+                ))
+            ),
             (
                 menu_button(text("Info")).on_press(AppMessage::None),
                 menu_tpl(menu_items!(
@@ -653,6 +2523,25 @@ impl App {
             .into()
     }
 
+    /// Menu items for picking one of `values`, the selected one is marked.
+    fn choice_items<'a>(
+        values: &[u32],
+        selected: u32,
+        label: impl Fn(u32) -> String,
+        on_select: impl Fn(u32) -> AppMessage,
+    ) -> Vec<Item<'a, AppMessage, Theme, Renderer>> {
+        values
+            .iter()
+            .map(|&value| {
+                let marker = if value == selected { "● " } else { "   " };
+                Item::new(
+                    menu_button(text(format!("{}{}", marker, label(value))).width(Length::Fill))
+                        .on_press(on_select(value)),
+                )
+            })
+            .collect()
+    }
+
     fn get_window_view(&self) -> Option<Element<'_, AppMessage>> {
         if let Some(window_content) = self.window_manager.get_window() {
             let (custom_body, on_okay): (Option<Element<'_, AppMessage>>, Option<AppMessage>) =
@@ -660,23 +2549,13 @@ impl App {
                     WindowType::EntryEditor => (
                         Some(self.create_entity_add_window_body()),
                         if self.is_data_entry_valid() {
-                            Some(AppMessage::AddEntry((
-                                DataEntry {
-                                    id: self.editing_id.unwrap_or(uuid::Uuid::new_v4()),
-                                    key: self.key_input_value.clone(),
-                                    description: parse_description_elements(
-                                        self.decription_input_value.clone(),
-                                    ),
-                                    description_raw: self.decription_input_value.clone(),
-                                },
-                                Some(window_content.clone()),
-                            )))
+                            Some(AppMessage::AddEntry(Some(window_content.clone())))
                         } else {
                             Some(AppMessage::OpenWindow(WindowContent::new(
                                 WindowType::Warning,
                                 "Invalid Input data".to_string(),
                                 WindowContentType::StringContent(
-                                    "Key and Description cannot be empty.".to_string(),
+                                    "Key and Description cannot be empty and every custom field needs a name and a value that fits its type.".to_string(),
                                 ),
                                 None,
                                 false,
@@ -685,6 +2564,58 @@ impl App {
                             )))
                         },
                     ),
+                    WindowType::Password => (
+                        Some(self.create_password_window_body(window_content)),
+                        Some(AppMessage::SubmitPassword(window_content.clone())),
+                    ),
+                    WindowType::Generator => (
+                        Some(generator(
+                            Length::Fixed(85.0),
+                            &self.generator_options,
+                            &self.generated_value,
+                            AppMessage::GeneratorChange,
+                        )),
+                        Some(AppMessage::InsertGenerated(window_content.clone())),
+                    ),
+                    WindowType::ShareExport => (
+                        Some(self.create_share_export_window_body()),
+                        Some(AppMessage::SaveShares(window_content.clone())),
+                    ),
+                    WindowType::ShareRecovery => (
+                        Some(share_recovery(
+                            &self.recovery_shares,
+                            &self.share_text_value,
+                            Self::share_change_message,
+                        )),
+                        Some(AppMessage::CombineShares(window_content.clone())),
+                    ),
+                    WindowType::Backups => (Some(self.create_backups_window_body()), None),
+                    WindowType::History => (Some(self.create_history_window_body()), None),
+                    WindowType::MoveToFolder => (
+                        Some(self.create_move_window_body()),
+                        Some(AppMessage::MoveToFolder(window_content.clone())),
+                    ),
+                    WindowType::Import => (
+                        Some(self.create_import_window_body()),
+                        Some(AppMessage::PickImportFile(window_content.clone())),
+                    ),
+                    WindowType::CsvImport => (
+                        Some(self.create_csv_import_window_body()),
+                        Some(match self.csv_mapping.key {
+                            Some(_) => AppMessage::ImportCsvRows(window_content.clone()),
+                            None => AppMessage::OpenWindow(WindowContent::new(
+                                WindowType::Warning,
+                                "No key column".to_string(),
+                                WindowContentType::StringContent(
+                                    "Select the column used as the key of the entries.".to_string(),
+                                ),
+                                None,
+                                false,
+                                true,
+                                None,
+                            )),
+                        }),
+                    ),
                     WindowType::Image => (
                         self.create_image_view_window_body(match window_content.content.clone() {
                             WindowContentType::ImageContant(image) => Some(image.image),
@@ -724,6 +2655,10 @@ impl App {
                     true,
                 ))),
                 AppMessage::CloseWindow((Some(window_content.clone()), false)),
+                window_content
+                    .on_discard
+                    .as_ref()
+                    .map(|_| AppMessage::DiscardChanges(window_content.clone())),
                 custom_body,
             ))
         } else {
@@ -734,19 +2669,348 @@ impl App {
     fn create_entity_add_window_body(&self) -> Element<'_, AppMessage> {
         entity_edit(
             Length::Fixed(85.0),
-            self.key_input_value.as_str(),
+            EntryInputs {
+                key: self.key_input_value.as_str(),
+                description: self.decription_input_value.as_str(),
+                tags: TagInputs {
+                    tags: &self.tags_input_value,
+                    input: &self.tag_input_value,
+                    known: &self.app_data.tags(),
+                },
+                fields: &self.fields_input_value,
+            },
             !self.is_key_input_valid(),
-            self.decription_input_value.as_str(),
             !self.is_description_input_valid(),
             |change: InputChange| match change {
                 InputChange::Key(value) => AppMessage::InputChange(InputType::Key, value),
                 InputChange::Description(value) => {
                     AppMessage::InputChange(InputType::Description, value)
                 }
+                InputChange::Generate => AppMessage::OpenGenerator,
+                InputChange::TagInput(value) => AppMessage::InputChange(InputType::Tag, value),
+                InputChange::AddTags(value) => AppMessage::AddTags(value),
+                InputChange::RemoveTag(tag) => AppMessage::RemoveTag(tag),
+                InputChange::Field(change) => AppMessage::FieldChange(change),
             },
         )
     }
 
+    fn create_password_window_body(
+        &self,
+        window_content: &WindowContent<AppMessage>,
+    ) -> Element<'_, AppMessage> {
+        let on_submit = AppMessage::SubmitPassword(window_content.clone());
+        let key_file_label = match &self.key_file_path {
+            Some(path) => path.display().to_string(),
+            None => "No key file selected".to_string(),
+        };
+
+        let (message, inputs) = match &window_content.content {
+            WindowContentType::PasswordContent(PasswordPurpose::Change(_)) => {
+                let factors = self.unlock_factors_value;
+                let current_factors = self
+                    .app_data
+                    .unlock_factors()
+                    .unwrap_or(UnlockFactors::Password);
+                (
+                    "Enter the current password and choose how to unlock the file from now on.",
+                    PasswordInputs {
+                        factors: Some(factors),
+                        current: current_factors
+                            .needs_password()
+                            .then_some(self.password_current_value.as_str()),
+                        password: factors
+                            .needs_password()
+                            .then_some(self.password_input_value.as_str()),
+                        confirm: factors
+                            .needs_password()
+                            .then_some(self.password_confirm_value.as_str()),
+                        key_file: factors.needs_key_file().then_some(key_file_label.as_str()),
+                        memory_mib: Some(self.kdf_memory_value.as_str()),
+                        iterations: Some(self.kdf_iterations_value.as_str()),
+                    },
+                )
+            }
+            WindowContentType::PasswordContent(PasswordPurpose::Create(_)) => {
+                let factors = self.unlock_factors_value;
+                (
+                    "Choose how to unlock the file, it will be encrypted with it.",
+                    PasswordInputs {
+                        factors: Some(factors),
+                        password: factors
+                            .needs_password()
+                            .then_some(self.password_input_value.as_str()),
+                        confirm: factors
+                            .needs_password()
+                            .then_some(self.password_confirm_value.as_str()),
+                        key_file: factors.needs_key_file().then_some(key_file_label.as_str()),
+                        ..Default::default()
+                    },
+                )
+            }
+            _ => {
+                let factors = match &self.locked_vault {
+                    Some(vault) => vault.header.factors,
+                    None => UnlockFactors::Password,
+                };
+                (
+                    "This file is encrypted, unlock it to continue.",
+                    PasswordInputs {
+                        password: factors
+                            .needs_password()
+                            .then_some(self.password_input_value.as_str()),
+                        key_file: factors.needs_key_file().then_some(key_file_label.as_str()),
+                        ..Default::default()
+                    },
+                )
+            }
+        };
+
+        let is_create = matches!(
+            window_content.content,
+            WindowContentType::PasswordContent(PasswordPurpose::Create(_))
+                | WindowContentType::PasswordContent(PasswordPurpose::Change(_))
+        );
+
+        password_form(
+            Length::Fixed(85.0),
+            message,
+            inputs,
+            is_create && !self.is_new_credentials_valid(),
+            self.kdf_input_params().is_none(),
+            Self::password_change_message,
+            on_submit,
+        )
+    }
+
+    fn password_change_message(change: PasswordChange) -> AppMessage {
+        match change {
+            PasswordChange::Current(value) => {
+                AppMessage::InputChange(InputType::PasswordCurrent, value)
+            }
+            PasswordChange::Password(value) => AppMessage::InputChange(InputType::Password, value),
+            PasswordChange::Confirm(value) => {
+                AppMessage::InputChange(InputType::PasswordConfirm, value)
+            }
+            PasswordChange::Memory(value) => AppMessage::InputChange(InputType::KdfMemory, value),
+            PasswordChange::Iterations(value) => {
+                AppMessage::InputChange(InputType::KdfIterations, value)
+            }
+            PasswordChange::Factors(factors) => AppMessage::SelectUnlockFactors(factors),
+            PasswordChange::BrowseKeyFile => AppMessage::PickKeyFile(false),
+            PasswordChange::CreateKeyFile => AppMessage::CreateKeyFile,
+        }
+    }
+
+    fn create_csv_import_window_body(&self) -> Element<'_, AppMessage> {
+        let data_rows = self
+            .csv_records
+            .len()
+            .saturating_sub(usize::from(self.csv_mapping.has_header));
+        csv_import(
+            Length::Fixed(85.0),
+            &self.csv_mapping.columns(&self.csv_records),
+            &self.csv_mapping,
+            self.csv_mode,
+            data_rows,
+            &self.csv_mapping.preview(&self.csv_records),
+            AppMessage::CsvImportChange,
+        )
+    }
+
+    /// Counts of the import followed by the rows that were left out.
+    fn csv_report(added: usize, merged: usize, skipped: &[csv_import::SkippedRow]) -> String {
+        format!(
+            "{} entries added, {} merged.{}",
+            added,
+            merged,
+            Self::skipped_report(skipped)
+        )
+    }
+
+    /// The rows an import left out, listing at most a screenful. Empty when none were.
+    fn skipped_report(skipped: &[csv_import::SkippedRow]) -> String {
+        const LISTED_ROWS: usize = 15;
+
+        let mut report = String::new();
+        if !skipped.is_empty() {
+            report.push_str(&format!("\n{} rows were skipped:", skipped.len()));
+            for row in skipped.iter().take(LISTED_ROWS) {
+                report.push_str(&format!("\n{}", row));
+            }
+            if skipped.len() > LISTED_ROWS {
+                report.push_str(&format!("\n... and {} more", skipped.len() - LISTED_ROWS));
+            }
+        }
+        report
+    }
+
+    fn create_share_export_window_body(&self) -> Element<'_, AppMessage> {
+        share_export(
+            Length::Fixed(85.0),
+            self.share_entry
+                .as_ref()
+                .map(|entry| entry.key.as_str())
+                .unwrap_or_default(),
+            &self.share_count_value,
+            &self.share_threshold_value,
+            self.share_input_params().is_none(),
+            &self.exported_shares,
+            Self::share_change_message,
+        )
+    }
+
+    fn share_change_message(change: ShareChange) -> AppMessage {
+        match change {
+            ShareChange::Count(value) => AppMessage::InputChange(InputType::ShareCount, value),
+            ShareChange::Threshold(value) => {
+                AppMessage::InputChange(InputType::ShareThreshold, value)
+            }
+            ShareChange::Split => AppMessage::SplitEntry,
+            ShareChange::Copy(value) => AppMessage::CopyToClipboard(value),
+            ShareChange::Text(value) => AppMessage::InputChange(InputType::ShareText, value),
+            ShareChange::AddText => AppMessage::AddShareText,
+            ShareChange::BrowseFiles => AppMessage::PickShareFiles,
+        }
+    }
+
+    /// Share count and threshold from the inputs, at least two shares are needed to rebuild.
+    fn share_input_params(&self) -> Option<(u8, u8)> {
+        let count = self.share_count_value.trim().parse::<u8>().ok()?;
+        let threshold = self.share_threshold_value.trim().parse::<u8>().ok()?;
+        (threshold >= 2 && threshold <= count).then_some((count, threshold))
+    }
+
+    /// Splits the entry being exported into the printable text of each share.
+    fn split_share_entry(&self) -> io::Result<Vec<String>> {
+        let entry = self
+            .share_entry
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No entry selected"))?;
+        let (count, threshold) = self.share_input_params().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Threshold must be at least 2 and at most the number of shares (255 at most)",
+            )
+        })?;
+
+        Ok(
+            shamir::split(entry.to_share_text().as_bytes(), threshold, count)?
+                .iter()
+                .map(|share| share.to_text(&entry.key))
+                .collect(),
+        )
+    }
+
+    /// Adds a share for recovery, ignoring repeats and rejecting shares of another entry.
+    fn add_recovery_share(&mut self, share: Share) -> io::Result<()> {
+        if let Some(first) = self.recovery_shares.first()
+            && (first.threshold != share.threshold
+                || first.count != share.count
+                || first.data.len() != share.data.len())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "This share belongs to a different entry",
+            ));
+        }
+        if !self
+            .recovery_shares
+            .iter()
+            .any(|existing| existing.index == share.index)
+        {
+            self.recovery_shares.push(share);
+        }
+        Ok(())
+    }
+
+    fn share_error_window(error: io::Error) -> AppMessage {
+        AppMessage::OpenWindow(WindowContent::new(
+            WindowType::Error,
+            "Share error".to_string(),
+            WindowContentType::StringContent(error.to_string()),
+            None,
+            false,
+            true,
+            None,
+        ))
+    }
+
+    fn create_backups_window_body(&self) -> Element<'_, AppMessage> {
+        if self.backups.is_empty() {
+            return text("No backups yet, one is made every time the file is saved.")
+                .size(16)
+                .into();
+        }
+
+        let list = self
+            .backups
+            .iter()
+            .fold(column![].spacing(10), |list, backup| {
+                list.push(
+                    row![
+                        text(format!("{} UTC", backup.saved_at))
+                            .size(16)
+                            .width(Length::Fill),
+                        text(format!("{:.1} KiB", backup.size as f64 / 1024.0)).size(14),
+                        button(text("Restore"))
+                            .on_press(AppMessage::RestoreBackup((backup.path.clone(), false))),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                )
+            });
+        scrollable(list).height(Length::Shrink).into()
+    }
+
+    fn create_move_window_body(&self) -> Element<'_, AppMessage> {
+        let folders = self.app_data.folders().into_iter().fold(
+            column![].spacing(5),
+            |folders, (folder, _)| {
+                folders.push(
+                    button(text(folder.clone()).size(14))
+                        .style(button::text)
+                        .on_press(AppMessage::InputChange(InputType::Folder, folder)),
+                )
+            },
+        );
+
+        column![
+            text("Folders are separated by \"/\", like House/Garage. Leave it empty to take the entry out of its folder.").size(16),
+            text_input("Folder", &self.folder_input_value)
+                .on_input(|value| AppMessage::InputChange(InputType::Folder, value)),
+            scrollable(folders).height(Length::Shrink),
+        ]
+        .spacing(15)
+        .into()
+    }
+
+    fn create_history_window_body(&self) -> Element<'_, AppMessage> {
+        match self.history_entry.and_then(|id| self.find_entry(id)) {
+            Some(entry) => history(
+                entry,
+                move |index| AppMessage::RevertEntry((entry.id, index)),
+                &self.theme,
+            ),
+            None => text("The entry no longer exists.").size(16).into(),
+        }
+    }
+
+    fn create_import_window_body(&self) -> Element<'_, AppMessage> {
+        column![
+            pick_list(
+                ImportSource::ALL,
+                Some(self.import_source),
+                AppMessage::SelectImportSource
+            )
+            .width(Length::Fill),
+            text(self.import_source.description()).size(16),
+            text("Okay picks the file, its entries are added to the current ones.").size(14),
+        ]
+        .spacing(15)
+        .into()
+    }
+
     fn create_image_view_window_body(&self, image_path: Option<String>) -> Element<'_, AppMessage> {
         match image_path {
             Some(path) => iced::widget::image(path)
@@ -786,29 +3050,160 @@ impl App {
     }
 
     fn is_data_entry_valid(&self) -> bool {
-        self.is_key_input_valid() && self.is_description_input_valid()
+        self.is_key_input_valid()
+            && self.is_description_input_valid()
+            && self.fields_input_value.iter().all(CustomField::is_valid)
+    }
+
+    fn is_new_credentials_valid(&self) -> bool {
+        let factors = self.unlock_factors_value;
+        let is_password_valid = !self.password_input_value.is_empty()
+            && self.password_input_value == self.password_confirm_value;
+
+        (!factors.needs_password() || is_password_valid)
+            && (!factors.needs_key_file() || self.key_file_path.is_some())
+    }
+
+    fn input_credentials(&self, factors: UnlockFactors) -> io::Result<Credentials> {
+        let key_file = match (factors.needs_key_file(), &self.key_file_path) {
+            (true, Some(path)) => Some(vault::read_key_file(path)?),
+            (true, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Select a key file first",
+                ));
+            }
+            (false, _) => None,
+        };
+
+        Ok(Credentials {
+            factors,
+            password: self.password_input_value.clone(),
+            key_file,
+        })
+    }
+
+    /// Tells what changed in an upgraded file and offers to save it in the current format.
+    fn migration_window(report: MigrationReport, on_save: AppMessage) -> AppMessage {
+        AppMessage::OpenWindow(WindowContent::new(
+            WindowType::Info,
+            "File upgraded".to_string(),
+            WindowContentType::StringContent(format!(
+                "{}\n\nSave the upgraded file now? The file on disk stays at version {} until it is saved.",
+                report, report.from_version
+            )),
+            Some(500),
+            true,
+            true,
+            Some(on_save),
+        ))
+    }
+
+    fn invalid_credentials_window() -> AppMessage {
+        AppMessage::OpenWindow(WindowContent::new(
+            WindowType::Warning,
+            "Invalid password".to_string(),
+            WindowContentType::StringContent(
+                "Enter the password twice and select a key file if the chosen method needs one."
+                    .to_string(),
+            ),
+            None,
+            false,
+            true,
+            None,
+        ))
+    }
+
+    /// Asks for whatever the locked file needs, starting with the key file.
+    fn unlock_flow(vault: &LockedVault) -> Task<AppMessage> {
+        if vault.header.factors.needs_key_file() {
+            Task::done(AppMessage::PickKeyFile(true))
+        } else {
+            Task::done(Self::unlock_window())
+        }
+    }
+
+    fn kdf_input_params(&self) -> Option<KdfParams> {
+        let memory_mib = self.kdf_memory_value.trim().parse::<u32>().ok()?;
+        let iterations = self.kdf_iterations_value.trim().parse::<u32>().ok()?;
+
+        let kdf = KdfParams {
+            memory_kib: memory_mib.checked_mul(1024)?,
+            iterations,
+            ..KdfParams::default()
+        };
+        kdf.is_within_bounds().then_some(kdf)
+    }
+
+    /// A locked file has no entries in memory, saving would replace it with an empty one.
+    fn locked_save_window() -> AppMessage {
+        AppMessage::OpenWindow(WindowContent::new(
+            WindowType::Warning,
+            "Cannot save".to_string(),
+            WindowContentType::StringContent("The file is locked, unlock it first.".to_string()),
+            None,
+            false,
+            true,
+            None,
+        ))
+    }
+
+    fn unlock_window() -> AppMessage {
+        AppMessage::OpenWindow(WindowContent::new(
+            WindowType::Password,
+            "Unlock File".to_string(),
+            WindowContentType::PasswordContent(PasswordPurpose::Unlock),
+            Some(500),
+            true,
+            true,
+            None,
+        ))
+    }
+
+    fn find_entry(&self, id: uuid::Uuid) -> Option<&DataEntry> {
+        self.app_data.entries.iter().find(|entry| entry.id == id)
     }
 
     fn search_entries(&mut self) {
+        self.revealed_secret = None;
+
+        // Tags and folders that no entry has any more cannot be unselected, so they are dropped.
+        let tags = self.app_data.tags();
+        self.tag_filter.retain(|tag| tags.contains(tag));
+        if let Some(folder) = &self.selected_folder
+            && !self
+                .app_data
+                .entries
+                .iter()
+                .any(|entry| entry.is_in_folder(folder))
+        {
+            self.selected_folder = None;
+        }
+
         if self.search_input_value.is_empty() {
-            self.entries_sorted = self.app_data.entries.clone();
+            self.entries_sorted = (0..self.app_data.entries.len()).collect();
         } else {
             let search_query = self.search_input_value.as_str();
             let fuse = &self.fuse;
 
-            let mut scored_entries: Vec<(DataEntry, f64)> = self
+            let mut scored_entries: Vec<(usize, f64)> = self
                 .app_data
                 .entries
                 .iter()
-                .filter_map(|entry| {
+                .enumerate()
+                .filter_map(|(index, entry)| {
                     let search_text = match self.searched_input.unwrap() {
-                        InputType::Key => entry.key.as_str(),
-                        _ => entry.description_raw.as_str(),
+                        InputType::Key => entry.key.clone(),
+                        _ => {
+                            let mut search_text = searchable_description(&entry.description);
+                            search_text.push_str(&searchable_fields(&entry.fields));
+                            search_text
+                        }
                     };
-                    let score_result = fuse.search_text_in_string(search_query, search_text);
+                    let score_result = fuse.search_text_in_string(search_query, &search_text);
 
                     match score_result {
-                        Some(result) if result.score <= 0.5 => Some((entry.clone(), result.score)),
+                        Some(result) if result.score <= 0.5 => Some((index, result.score)),
                         _ => None,
                     }
                 })
@@ -819,8 +3214,40 @@ impl App {
 
             self.entries_sorted = scored_entries
                 .into_iter()
-                .map(|(entry, _score)| entry)
+                .map(|(index, _score)| index)
                 .collect();
         }
+
+        if let Some(folder) = &self.selected_folder {
+            let entries = &self.app_data.entries;
+            self.entries_sorted
+                .retain(|&index| entries[index].is_in_folder(folder));
+        }
+        if !self.tag_filter.is_empty() {
+            let entries = &self.app_data.entries;
+            self.entries_sorted.retain(|&index| {
+                self.tag_match
+                    .matches(&entries[index].tags, &self.tag_filter)
+            });
+        }
+
+        // Stable sorts, so equal entries stay in relevance or file order.
+        let entries = &self.app_data.entries;
+        match self.sort_order {
+            SortOrder::Relevance => {}
+            SortOrder::Key => self
+                .entries_sorted
+                .sort_by_cached_key(|&index| entries[index].key.to_lowercase()),
+            SortOrder::Created => self
+                .entries_sorted
+                .sort_by_key(|&index| std::cmp::Reverse(entries[index].created)),
+            SortOrder::Modified => self
+                .entries_sorted
+                .sort_by_key(|&index| std::cmp::Reverse(entries[index].modified)),
+        }
     }
 }
+
+fn clipboard_digest(value: &str) -> [u8; 32] {
+    Sha256::digest(value.as_bytes()).into()
+}
@@ -1,8 +1,16 @@
+use std::borrow::Cow;
+
 use iced::widget::{rich_text, span};
 use iced::{Element, Theme};
 use regex::Regex;
 
+use crate::secret_string::SecretString;
+use crate::totp::{DEFAULT_DIGITS, DEFAULT_PERIOD, Totp, TotpAlgorithm};
 use crate::utils::shift_hue;
+use crate::vault::parse_attributes;
+
+/// Shown instead of a secret, always the same length so it does not give the length away.
+pub const SECRET_MASK: &str = "••••••••";
 
 #[derive(Clone, Debug)]
 pub struct Link {
@@ -22,33 +30,50 @@ pub struct DescriptionSound {
     pub sound: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptionSecret {
+    pub text: String,
+    pub secret: SecretString,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptionTotp {
+    pub text: String,
+    pub totp: Totp,
+}
+
 #[derive(Clone, Debug)]
 pub enum DescriptionElement {
-    Text(String),
+    Text(SecretString),
     Link(Link),
     Image(DescriptionImage),
     Sound(DescriptionSound),
+    Secret(DescriptionSecret),
+    Totp(DescriptionTotp),
 }
 
-pub fn parse_description_elements(description: String) -> Vec<DescriptionElement> {
-    let re = Regex::new(r#"<(link|image|sound)="([^"]+)" text="([^"]+)">"#).unwrap();
+pub fn parse_description_elements(description: &str) -> Vec<DescriptionElement> {
+    let re = Regex::new(
+        r#"<(link|image|sound|secret|totp)="([^"]+)" text="([^"]+)"((?:\s+\w+="[^"]*")*)>"#,
+    )
+    .unwrap();
 
     let mut elements = Vec::new();
     let mut last_match = 0;
 
-    for cap in re.captures_iter(description.as_str()) {
+    for cap in re.captures_iter(description) {
         let m = cap.get(0).unwrap();
 
         if m.start() > last_match {
             elements.push(DescriptionElement::Text(
-                description[last_match..m.start()].to_string(),
+                description[last_match..m.start()].into(),
             ));
         }
 
         // 2. Parse the specific tag type
         let tag_type = &cap[1];
-        let val = cap[2].to_string();
-        let text = cap[3].to_string();
+        let val = unescape_attribute(&cap[2]);
+        let text = unescape_attribute(&cap[3]);
 
         match tag_type {
             "link" => elements.push(DescriptionElement::Link(Link {
@@ -63,7 +88,34 @@ pub fn parse_description_elements(description: String) -> Vec<DescriptionElement
                 sound: val,
                 text,
             })),
-            _ => elements.push(DescriptionElement::Text(m.as_str().to_string())),
+            "secret" => elements.push(DescriptionElement::Secret(DescriptionSecret {
+                secret: val.into(),
+                text,
+            })),
+            "totp" => {
+                let options = parse_attributes(cap.get(4).map_or("", |m| m.as_str()));
+                elements.push(DescriptionElement::Totp(DescriptionTotp {
+                    totp: Totp {
+                        secret: val.into(),
+                        digits: options
+                            .get("digits")
+                            .and_then(|value| value.parse().ok())
+                            .filter(|digits| (6..=8).contains(digits))
+                            .unwrap_or(DEFAULT_DIGITS),
+                        period: options
+                            .get("period")
+                            .and_then(|value| value.parse().ok())
+                            .filter(|period| *period > 0)
+                            .unwrap_or(DEFAULT_PERIOD),
+                        algorithm: options
+                            .get("algorithm")
+                            .and_then(|value| TotpAlgorithm::from_name(value))
+                            .unwrap_or_default(),
+                    },
+                    text,
+                }))
+            }
+            _ => elements.push(DescriptionElement::Text(m.as_str().into())),
         }
 
         last_match = m.end();
@@ -71,66 +123,200 @@ pub fn parse_description_elements(description: String) -> Vec<DescriptionElement
 
     // 3. Push any remaining text after the last tag
     if last_match < description.len() {
-        elements.push(DescriptionElement::Text(
-            description[last_match..].to_string(),
-        ));
+        elements.push(DescriptionElement::Text(description[last_match..].into()));
     }
 
     elements
 }
 
-pub fn serialize_description_elements(elements: Vec<DescriptionElement>) -> String {
+pub fn serialize_description_elements(elements: &[DescriptionElement]) -> SecretString {
+    // Collected first so the result is allocated once and no partial copy is left behind.
+    let parts: Vec<Cow<'_, str>> = elements
+        .iter()
+        .flat_map(|element| match element {
+            DescriptionElement::Text(text) => vec![Cow::Borrowed(text.as_str())],
+            DescriptionElement::Link(ld) => vec![Cow::Owned(format!(
+                "<link=\"{}\" text=\"{}\">",
+                escape_attribute(&ld.link),
+                escape_attribute(&ld.text)
+            ))],
+            DescriptionElement::Image(image) => vec![Cow::Owned(format!(
+                "<image=\"{}\" text=\"{}\">",
+                escape_attribute(&image.image),
+                escape_attribute(&image.text)
+            ))],
+            DescriptionElement::Sound(sound) => vec![Cow::Owned(format!(
+                "<sound=\"{}\" text=\"{}\">",
+                escape_attribute(&sound.sound),
+                escape_attribute(&sound.text)
+            ))],
+            DescriptionElement::Secret(secret) => vec![
+                Cow::Borrowed("<secret=\""),
+                escape_attribute(&secret.secret),
+                Cow::Owned(format!("\" text=\"{}\">", escape_attribute(&secret.text))),
+            ],
+            DescriptionElement::Totp(totp) => vec![
+                Cow::Borrowed("<totp=\""),
+                escape_attribute(&totp.totp.secret),
+                Cow::Owned(format!(
+                    "\" text=\"{}\"{}>",
+                    escape_attribute(&totp.text),
+                    totp_options(&totp.totp)
+                )),
+            ],
+        })
+        .collect();
+
+    let mut serialized = String::with_capacity(parts.iter().map(|part| part.len()).sum());
+    for part in &parts {
+        serialized.push_str(part);
+    }
+    SecretString::from(serialized)
+}
+
+/// The description as it is searched, secrets are left out so they cannot be guessed by
+/// searching for them.
+pub fn searchable_description(elements: &[DescriptionElement]) -> SecretString {
+    let visible: Vec<DescriptionElement> = elements
+        .iter()
+        .filter(|element| {
+            !matches!(
+                element,
+                DescriptionElement::Secret(_) | DescriptionElement::Totp(_)
+            )
+        })
+        .cloned()
+        .collect();
+    serialize_description_elements(&visible)
+}
+
+/// The description as readable text, used when copying it. Secrets only show their label,
+/// they are copied on their own by clicking them.
+pub fn plain_description(elements: &[DescriptionElement]) -> String {
     elements
-        .into_iter()
+        .iter()
         .map(|element| match element {
-            DescriptionElement::Text(text) => text,
-            DescriptionElement::Link(ld) => format!("<link=\"{}\" text=\"{}\">", ld.link, ld.text),
-            DescriptionElement::Image(image) => {
-                format!("<image=\"{}\" text=\"{}\">", image.image, image.text)
-            }
-            DescriptionElement::Sound(sound) => {
-                format!("<sound=\"{}\" text=\"{}\">", sound.sound, sound.text)
-            }
+            DescriptionElement::Text(text) => text.to_string(),
+            DescriptionElement::Link(ld) => format!("{} ({})", ld.text, ld.link),
+            DescriptionElement::Image(image) => image.text.clone(),
+            DescriptionElement::Sound(sound) => sound.text.clone(),
+            DescriptionElement::Secret(secret) => secret.text.clone(),
+            DescriptionElement::Totp(totp) => totp.text.clone(),
         })
         .collect::<Vec<String>>()
         .join("")
 }
 
 pub fn description_component<'a, Message>(
-    description_elements: Vec<DescriptionElement>,
+    description_elements: &'a [DescriptionElement],
     on_click: impl Fn(DescriptionElement) -> Message + 'a,
+    revealed: Option<&'a DescriptionSecret>,
+    unix_time: u64,
     theme: &Theme,
 ) -> Element<'a, Message>
 where
     Message: Clone + 'a,
 {
+    // Spans borrow the entry and link to the element index, so rendering does not copy secrets.
     let spans: Vec<_> = description_elements
-        .into_iter()
-        .map(|value| match value.clone() {
-            DescriptionElement::Text(content) => span(content),
-            DescriptionElement::Link(ld) => span(ld.text.clone())
-                .color(theme.extended_palette().primary.strong.color)
-                .underline(true)
-                .link(value),
-            DescriptionElement::Image(image) => span(image.text.clone())
-                .color(shift_hue(
-                    theme.extended_palette().primary.strong.color,
-                    100.0,
-                ))
-                .underline(true)
-                .link(value),
-            DescriptionElement::Sound(sound) => span(sound.text.clone())
-                .color(shift_hue(
-                    theme.extended_palette().primary.strong.color,
-                    200.0,
-                ))
-                .underline(true)
-                .link(value),
+        .iter()
+        .enumerate()
+        .flat_map(|(index, value)| match value {
+            DescriptionElement::Text(content) => vec![span(content.as_str())],
+            DescriptionElement::Link(ld) => vec![
+                span(ld.text.as_str())
+                    .color(theme.extended_palette().primary.strong.color)
+                    .underline(true)
+                    .link(index),
+            ],
+            DescriptionElement::Image(image) => vec![
+                span(image.text.as_str())
+                    .color(shift_hue(
+                        theme.extended_palette().primary.strong.color,
+                        100.0,
+                    ))
+                    .underline(true)
+                    .link(index),
+            ],
+            DescriptionElement::Sound(sound) => vec![
+                span(sound.text.as_str())
+                    .color(shift_hue(
+                        theme.extended_palette().primary.strong.color,
+                        200.0,
+                    ))
+                    .underline(true)
+                    .link(index),
+            ],
+            DescriptionElement::Secret(secret) => {
+                let shown = if revealed == Some(secret) {
+                    secret.secret.as_str()
+                } else {
+                    SECRET_MASK
+                };
+                [secret.text.as_str(), ": ", shown]
+                    .into_iter()
+                    .map(|part| {
+                        span(part)
+                            .color(theme.extended_palette().secondary.strong.color)
+                            .link(index)
+                    })
+                    .collect()
+            }
+            DescriptionElement::Totp(totp) => {
+                let code = match totp.totp.code_at(unix_time) {
+                    Some(code) => format!(
+                        "{} ({}s)",
+                        group_digits(&code),
+                        totp.totp.seconds_left(unix_time)
+                    ),
+                    None => "invalid secret".to_string(),
+                };
+                vec![
+                    span(format!("{}: {}", totp.text, code))
+                        .color(theme.extended_palette().success.strong.color)
+                        .link(index),
+                ]
+            }
         })
         .collect();
 
     rich_text(spans)
         .wrapping(iced::widget::text::Wrapping::WordOrGlyph)
-        .on_link_click(on_click)
+        .on_link_click(move |index: usize| on_click(description_elements[index].clone()))
         .into()
 }
+
+/// Quotes would end the attribute early, so they are written as `&quot;`. `&` is escaped as
+/// well so a value that contains `&quot;` itself reads back unchanged.
+pub fn escape_attribute(value: &str) -> Cow<'_, str> {
+    if value.contains(['"', '&']) {
+        Cow::Owned(value.replace('&', "&amp;").replace('"', "&quot;"))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+pub fn unescape_attribute(value: &str) -> String {
+    value.replace("&quot;", "\"").replace("&amp;", "&")
+}
+
+/// Only the attributes that differ from the defaults are written.
+fn totp_options(totp: &Totp) -> String {
+    let mut options = String::new();
+    if totp.digits != DEFAULT_DIGITS {
+        options.push_str(&format!(" digits=\"{}\"", totp.digits));
+    }
+    if totp.period != DEFAULT_PERIOD {
+        options.push_str(&format!(" period=\"{}\"", totp.period));
+    }
+    if totp.algorithm != TotpAlgorithm::default() {
+        options.push_str(&format!(" algorithm=\"{}\"", totp.algorithm));
+    }
+    options
+}
+
+/// Splits a code in two halves for reading, `123456` becomes `123 456`.
+fn group_digits(code: &str) -> String {
+    let (first, second) = code.split_at(code.len() / 2);
+    format!("{} {}", first, second)
+}
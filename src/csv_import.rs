@@ -0,0 +1,339 @@
+use std::fmt;
+
+use crate::secret_string::SecretString;
+use crate::utils::DataEntry;
+
+/// Rows shown in the import preview.
+pub const PREVIEW_ROWS: usize = 5;
+
+/// One CSV record, `line` is the line it starts on so skipped rows can be found in the file.
+#[derive(Clone, Debug)]
+pub struct CsvRecord {
+    pub line: usize,
+    pub fields: Vec<SecretString>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Append,
+    Merge,
+}
+
+impl ImportMode {
+    pub const ALL: [ImportMode; 2] = [ImportMode::Append, ImportMode::Merge];
+}
+
+impl fmt::Display for ImportMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportMode::Append => write!(f, "Append as new entries"),
+            ImportMode::Merge => write!(f, "Merge into entries with the same key"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumn {
+    pub index: usize,
+    pub name: String,
+}
+
+impl fmt::Display for CsvColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkippedRow {
+    /// Where the row is in the file, like `Line 4`.
+    pub location: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SkippedRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.reason)
+    }
+}
+
+/// Which columns make up the key and the description of the imported entries.
+#[derive(Debug, Clone, Default)]
+pub struct CsvMapping {
+    pub has_header: bool,
+    pub key: Option<usize>,
+    /// Joined in column order when there is more than one.
+    pub description: Vec<usize>,
+}
+
+impl CsvMapping {
+    /// Takes a column named like a title as the key and everything else as the description.
+    pub fn guess(records: &[CsvRecord]) -> Self {
+        let header = records.first();
+        let key = header
+            .and_then(|header| {
+                header.fields.iter().position(|name| {
+                    matches!(
+                        name.trim().to_lowercase().as_str(),
+                        "key" | "name" | "title" | "account"
+                    )
+                })
+            })
+            .unwrap_or(0);
+        let width = column_count(records);
+
+        Self {
+            has_header: true,
+            key: (width > 0).then_some(key),
+            description: (0..width).filter(|&index| index != key).collect(),
+        }
+    }
+
+    pub fn columns(&self, records: &[CsvRecord]) -> Vec<CsvColumn> {
+        let header = self.header(records);
+        (0..column_count(records))
+            .map(|index| CsvColumn {
+                index,
+                name: header
+                    .and_then(|header| header.fields.get(index))
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| format!("Column {}", index + 1)),
+            })
+            .collect()
+    }
+
+    pub fn toggle_description(&mut self, index: usize, selected: bool) {
+        self.description.retain(|&column| column != index);
+        if selected {
+            self.description.push(index);
+            self.description.sort_unstable();
+        }
+    }
+
+    /// The first data rows as they would be imported.
+    pub fn preview(&self, records: &[CsvRecord]) -> Vec<Result<DataEntry, SkippedRow>> {
+        self.data_records(records)
+            .iter()
+            .take(PREVIEW_ROWS)
+            .map(|record| self.map_record(record, self.header(records)))
+            .collect()
+    }
+
+    /// Entries for every data row, rows that cannot be imported are returned with the reason.
+    pub fn map_records(&self, records: &[CsvRecord]) -> (Vec<DataEntry>, Vec<SkippedRow>) {
+        let mut entries = Vec::new();
+        let mut skipped = Vec::new();
+        for record in self.data_records(records) {
+            match self.map_record(record, self.header(records)) {
+                Ok(entry) => entries.push(entry),
+                Err(row) => skipped.push(row),
+            }
+        }
+        (entries, skipped)
+    }
+
+    fn map_record(
+        &self,
+        record: &CsvRecord,
+        header: Option<&CsvRecord>,
+    ) -> Result<DataEntry, SkippedRow> {
+        let skip = |reason: &str| SkippedRow {
+            location: format!("Line {}", record.line),
+            reason: reason.to_string(),
+        };
+
+        let key_column = self.key.ok_or_else(|| skip("no key column is selected"))?;
+        let key = record
+            .fields
+            .get(key_column)
+            .map(|key| single_line(key))
+            .ok_or_else(|| skip("the row has no key column"))?;
+        if key.trim().is_empty() {
+            return Err(skip("the key is empty"));
+        }
+
+        // With several columns each value is labeled with its header so they stay apart.
+        let labeled = header.is_some() && self.description.len() > 1;
+        let parts: Vec<SecretString> = self
+            .description
+            .iter()
+            .filter_map(|&index| {
+                let value = single_line(record.fields.get(index)?);
+                if value.trim().is_empty() {
+                    return None;
+                }
+                let name = header.and_then(|header| header.fields.get(index));
+                Some(match name {
+                    Some(name) if labeled => format!("{}: {}", name.trim(), value.trim()).into(),
+                    _ => value.trim().into(),
+                })
+            })
+            .collect();
+        let description = SecretString::from(
+            parts
+                .iter()
+                .map(SecretString::as_str)
+                .collect::<Vec<_>>()
+                .join(if labeled { ", " } else { " " }),
+        );
+
+        Ok(DataEntry::new(key.trim(), &description))
+    }
+
+    fn header<'a>(&self, records: &'a [CsvRecord]) -> Option<&'a CsvRecord> {
+        if self.has_header {
+            records.first()
+        } else {
+            None
+        }
+    }
+
+    fn data_records<'a>(&self, records: &'a [CsvRecord]) -> &'a [CsvRecord] {
+        if self.has_header && !records.is_empty() {
+            &records[1..]
+        } else {
+            records
+        }
+    }
+}
+
+/// Parses CSV as RFC 4180 describes it, quoted fields may hold separators, doubled quotes and
+/// line breaks. The separator is taken from the first line, spreadsheets in some locales write
+/// `;` or tabs instead of commas.
+pub fn parse_csv(text: &str) -> Vec<CsvRecord> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let separator = detect_separator(text);
+
+    let mut records = Vec::new();
+    let mut fields: Vec<SecretString> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut finish_record = |fields: &mut Vec<SecretString>, record_line: usize| {
+        // Blank lines are not records.
+        if fields.len() > 1 || fields.first().is_some_and(|field| !field.is_empty()) {
+            records.push(CsvRecord {
+                line: record_line,
+                fields: std::mem::take(fields),
+            });
+        }
+        fields.clear();
+    };
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            c if c == separator && !in_quotes => {
+                fields.push(std::mem::take(&mut field).into());
+            }
+            '\r' | '\n' if !in_quotes => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                fields.push(std::mem::take(&mut field).into());
+                finish_record(&mut fields, record_line);
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field.into());
+        finish_record(&mut fields, record_line);
+    }
+
+    records
+}
+
+fn detect_separator(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|separator| first_line.matches(*separator).count())
+        .filter(|separator| first_line.contains(*separator))
+        .unwrap_or(',')
+}
+
+fn column_count(records: &[CsvRecord]) -> usize {
+    records
+        .iter()
+        .map(|record| record.fields.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Entries are stored one per line, so line breaks inside a value become spaces.
+pub fn single_line(value: &str) -> SecretString {
+    value
+        .split(['\r', '\n'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(record: &CsvRecord) -> Vec<&str> {
+        record.fields.iter().map(SecretString::as_str).collect()
+    }
+
+    #[test]
+    fn quoted_fields_keep_separators_quotes_and_line_breaks() {
+        let records =
+            parse_csv("name,note\r\n\"a, b\",\"say \"\"hi\"\"\"\n\"multi\nline\",x\n\nlast,");
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(fields(&records[1]), ["a, b", "say \"hi\""]);
+        assert_eq!(fields(&records[2]), ["multi\nline", "x"]);
+        assert_eq!(records[2].line, 3);
+        assert_eq!(fields(&records[3]), ["last", ""]);
+        assert_eq!(records[3].line, 6);
+    }
+
+    #[test]
+    fn separator_is_detected_from_the_first_line() {
+        let records = parse_csv("\u{feff}name;password\nrouter;\"x;y\"");
+        assert_eq!(fields(&records[0]), ["name", "password"]);
+        assert_eq!(fields(&records[1]), ["router", "x;y"]);
+    }
+
+    #[test]
+    fn rows_are_mapped_and_skipped_with_a_reason() {
+        let records = parse_csv("Title,User,Password\nMail,me,\"p\nw\"\n,nobody,x\nSolo\n");
+        let mapping = CsvMapping::guess(&records);
+        assert_eq!(mapping.key, Some(0));
+        assert_eq!(mapping.description, [1, 2]);
+
+        let (entries, skipped) = mapping.map_records(&records);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key.as_str(), "Mail");
+        assert_eq!(
+            entries[0].to_line().as_str(),
+            "Mail:User\\: me, Password\\: p w"
+        );
+        assert_eq!(entries[1].to_line().as_str(), "Solo:");
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].to_string(), "Line 4: the key is empty");
+    }
+}
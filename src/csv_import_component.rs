@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use iced::Alignment::Center;
+use iced::Element;
+use iced::Length::{self, Fill};
+use iced::widget::{Column, checkbox, column, container, pick_list, row, scrollable, text};
+
+use crate::base_description_component::plain_description;
+use crate::csv_import::{CsvColumn, CsvMapping, ImportMode, SkippedRow};
+use crate::utils::DataEntry;
+
+#[derive(Debug, Clone)]
+pub enum CsvImportChange {
+    HasHeader(bool),
+    KeyColumn(CsvColumn),
+    DescriptionColumn(usize, bool),
+    Mode(ImportMode),
+}
+
+pub fn csv_import<'a, Message>(
+    label_width: Length,
+    columns: &[CsvColumn],
+    mapping: &CsvMapping,
+    mode: ImportMode,
+    row_count: usize,
+    preview: &[Result<DataEntry, SkippedRow>],
+    on_input: impl Fn(CsvImportChange) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let on_input = Rc::new(on_input);
+    let on_input_header = on_input.clone();
+    let on_input_key = on_input.clone();
+    let on_input_mode = on_input.clone();
+
+    let selected_key = mapping
+        .key
+        .and_then(|index| columns.iter().find(|column| column.index == index))
+        .cloned();
+
+    let description_columns =
+        columns
+            .iter()
+            .fold(row![].spacing(15), |description_columns, column| {
+                let on_input = on_input.clone();
+                let index = column.index;
+                description_columns.push(
+                    checkbox(mapping.description.contains(&index))
+                        .label(column.name.clone())
+                        .on_toggle(move |value| {
+                            on_input(CsvImportChange::DescriptionColumn(index, value))
+                        }),
+                )
+            });
+
+    let preview_rows = preview.iter().fold(column![].spacing(8), |rows, row| {
+        rows.push(match row {
+            Ok(entry) => text(format!(
+                "{}: {}",
+                entry.key.as_str(),
+                plain_description(&entry.description)
+            ))
+            .size(14),
+            Err(skipped) => text(format!("Skipped, {}", skipped))
+                .size(14)
+                .style(text::danger),
+        })
+    });
+
+    let content: Column<'a, Message> = column![
+        text(format!(
+            "{} rows found. Pick the column used as the key and the columns joined into the description.",
+            row_count
+        ))
+        .size(16),
+        checkbox(mapping.has_header)
+            .label("First row is a header")
+            .on_toggle(move |value| on_input_header(CsvImportChange::HasHeader(value))),
+        row![
+            label("Key", label_width),
+            pick_list(columns.to_vec(), selected_key, move |column| {
+                on_input_key(CsvImportChange::KeyColumn(column))
+            })
+            .width(Fill),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![label("Description", label_width), description_columns.wrap()]
+            .spacing(10)
+            .align_y(Center),
+        row![
+            label("Import", label_width),
+            pick_list(ImportMode::ALL, Some(mode), move |value| {
+                on_input_mode(CsvImportChange::Mode(value))
+            })
+            .width(Fill),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        text("Preview").size(16),
+        scrollable(preview_rows).height(150),
+    ]
+    .spacing(15);
+
+    content.into()
+}
+
+fn label<'a, Message>(value: &'a str, width: Length) -> Element<'a, Message>
+where
+    Message: 'a,
+{
+    container(text(value).size(16).width(width).align_y(Center))
+        .padding(5)
+        .into()
+}
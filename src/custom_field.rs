@@ -0,0 +1,197 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_description_component::{DescriptionElement, DescriptionSecret, Link};
+use crate::secret_string::SecretString;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    Text,
+    Secret,
+    Url,
+    Date,
+    Number,
+    FilePath,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 6] = [
+        FieldKind::Text,
+        FieldKind::Secret,
+        FieldKind::Url,
+        FieldKind::Date,
+        FieldKind::Number,
+        FieldKind::FilePath,
+    ];
+
+    /// The name written to the file, the same one JSON exports use.
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldKind::Text => "text",
+            FieldKind::Secret => "secret",
+            FieldKind::Url => "url",
+            FieldKind::Date => "date",
+            FieldKind::Number => "number",
+            FieldKind::FilePath => "filepath",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Shown in an empty value input, it tells what `is_valid` accepts.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            FieldKind::Text => "Value",
+            FieldKind::Secret => "Secret value",
+            FieldKind::Url => "https://example.com",
+            FieldKind::Date => "YYYY-MM-DD",
+            FieldKind::Number => "42",
+            FieldKind::FilePath => "Path to a file",
+        }
+    }
+
+    pub fn is_valid(self, value: &str) -> bool {
+        let value = value.trim();
+        match self {
+            FieldKind::Text | FieldKind::Secret | FieldKind::FilePath => !value.is_empty(),
+            FieldKind::Url => value.split_once("://").is_some_and(|(scheme, rest)| {
+                !rest.is_empty()
+                    && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            }),
+            FieldKind::Date => is_valid_date(value),
+            FieldKind::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+        }
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldKind::Text => write!(f, "Text"),
+            FieldKind::Secret => write!(f, "Secret"),
+            FieldKind::Url => write!(f, "URL"),
+            FieldKind::Date => write!(f, "Date"),
+            FieldKind::Number => write!(f, "Number"),
+            FieldKind::FilePath => write!(f, "File path"),
+        }
+    }
+}
+
+/// A named value of an entry next to its description, like a serial number or a PIN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomField {
+    pub name: String,
+    pub kind: FieldKind,
+    pub value: SecretString,
+}
+
+impl CustomField {
+    pub fn is_valid(&self) -> bool {
+        !self.name.trim().is_empty() && self.kind.is_valid(&self.value)
+    }
+
+    /// The element the value is shown and clicked as, secrets are revealed and copied like
+    /// secrets in the description and URLs and files are opened like links.
+    pub fn as_element(&self) -> DescriptionElement {
+        match self.kind {
+            FieldKind::Secret => DescriptionElement::Secret(DescriptionSecret {
+                text: self.name.clone(),
+                secret: self.value.clone(),
+            }),
+            FieldKind::Url | FieldKind::FilePath => DescriptionElement::Link(Link {
+                text: self.value.to_string(),
+                link: self.value.to_string(),
+            }),
+            FieldKind::Text | FieldKind::Date | FieldKind::Number => {
+                DescriptionElement::Text(self.value.clone())
+            }
+        }
+    }
+}
+
+/// Names and values for searching, secret values are left out like secrets in descriptions.
+pub fn searchable_fields(fields: &[CustomField]) -> SecretString {
+    let mut searchable = SecretString::default();
+    for field in fields {
+        searchable.push_str(" ");
+        searchable.push_str(&field.name);
+        if field.kind != FieldKind::Secret {
+            searchable.push_str(" ");
+            searchable.push_str(&field.value);
+        }
+    }
+    searchable
+}
+
+/// `YYYY-MM-DD` with a day that exists in that month.
+fn is_valid_date(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_checked_against_their_kind() {
+        assert!(FieldKind::Url.is_valid("https://192.168.0.1/admin"));
+        assert!(FieldKind::Url.is_valid("smb://nas/share"));
+        assert!(!FieldKind::Url.is_valid("192.168.0.1"));
+        assert!(!FieldKind::Url.is_valid("://nas"));
+
+        assert!(FieldKind::Date.is_valid("2024-02-29"));
+        assert!(!FieldKind::Date.is_valid("2023-02-29"));
+        assert!(!FieldKind::Date.is_valid("2024-13-01"));
+        assert!(!FieldKind::Date.is_valid("24-01-01"));
+
+        assert!(FieldKind::Number.is_valid(" -1.5 "));
+        assert!(!FieldKind::Number.is_valid("NaN"));
+        assert!(!FieldKind::Number.is_valid("12a"));
+
+        assert!(FieldKind::FilePath.is_valid("C:\\keys\\spare.txt"));
+        assert!(!FieldKind::Text.is_valid("  "));
+    }
+
+    #[test]
+    fn kinds_round_trip_through_their_names() {
+        for kind in FieldKind::ALL {
+            assert_eq!(FieldKind::from_name(kind.name()), Some(kind));
+            assert_eq!(
+                serde_json::to_string(&kind).unwrap(),
+                format!("\"{}\"", kind.name())
+            );
+        }
+    }
+}
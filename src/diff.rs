@@ -0,0 +1,79 @@
+/// A run of words that are in both texts, only in the new one or only in the old one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffPart {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Compares `old` and `new` word by word, the words both share are found as their longest common
+/// subsequence. Neighbouring words of the same kind are joined with a space.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffPart> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+
+    // `common[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`.
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut parts = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push_word(&mut parts, DiffPart::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            push_word(&mut parts, DiffPart::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push_word(&mut parts, DiffPart::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    parts
+}
+
+fn push_word(parts: &mut Vec<DiffPart>, part: DiffPart) {
+    match (parts.last_mut(), part) {
+        (Some(DiffPart::Same(words)), DiffPart::Same(word))
+        | (Some(DiffPart::Added(words)), DiffPart::Added(word))
+        | (Some(DiffPart::Removed(words)), DiffPart::Removed(word)) => {
+            words.push(' ');
+            words.push_str(&word);
+        }
+        (_, part) => parts.push(part),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_words_are_marked() {
+        assert_eq!(
+            word_diff("key under the blue mat", "key under the red flower pot"),
+            [
+                DiffPart::Same("key under the".to_string()),
+                DiffPart::Removed("blue mat".to_string()),
+                DiffPart::Added("red flower pot".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_texts_are_all_added_or_removed() {
+        assert_eq!(word_diff("", "a  b"), [DiffPart::Added("a b".to_string())]);
+        assert_eq!(word_diff("a b", ""), [DiffPart::Removed("a b".to_string())]);
+        assert!(word_diff("", "").is_empty());
+    }
+}
@@ -1,20 +1,53 @@
 use std::rc::Rc;
 
 use iced::Alignment::Center;
-use iced::Length;
-use iced::widget::{column, container, row, text, text_input};
+use iced::Length::{self, FillPortion};
+use iced::widget::{Column, button, column, container, pick_list, row, text, text_input};
 use iced::{Border, Element, Theme};
 
+use crate::custom_field::{CustomField, FieldKind};
+use crate::tag_component::tag_chip;
+
 pub enum InputChange {
     Key(String),
     Description(String),
+    Generate,
+    TagInput(String),
+    /// Comma separated tags to add.
+    AddTags(String),
+    RemoveTag(String),
+    Field(FieldChange),
+}
+
+/// Edits of the custom field rows, `usize` is the index of the row.
+#[derive(Debug, Clone)]
+pub enum FieldChange {
+    Name(usize, String),
+    Kind(usize, FieldKind),
+    Value(usize, String),
+    Add,
+    Remove(usize),
+}
+
+/// Values shown by `entity_edit`.
+pub struct EntryInputs<'a> {
+    pub key: &'a str,
+    pub description: &'a str,
+    pub tags: TagInputs<'a>,
+    pub fields: &'a [CustomField],
+}
+
+/// The tags of the edited entry, `known` are the tags of the other entries offered for reuse.
+pub struct TagInputs<'a> {
+    pub tags: &'a [String],
+    pub input: &'a str,
+    pub known: &'a [String],
 }
 
 pub fn entity_edit<'a, Message>(
     label_width: Length,
-    key_input: &str,
+    inputs: EntryInputs<'_>,
     is_key_invalid: bool,
-    description_input: &str,
     is_description_invalid: bool,
     on_input: impl Fn(InputChange) -> Message + 'a,
 ) -> Element<'a, Message>
@@ -24,23 +57,74 @@ where
     let on_input = Rc::new(on_input);
     let on_input_key = on_input.clone();
     let on_input_description = on_input.clone();
+    let on_input_tag = on_input.clone();
+    let tags = inputs.tags;
+
+    let tag_chips = tags
+        .tags
+        .iter()
+        .fold(row![].spacing(5), |chips, tag| {
+            chips.push(tag_chip(
+                format!("{} ×", tag),
+                true,
+                Some(on_input(InputChange::RemoveTag(tag.clone()))),
+            ))
+        })
+        .extend(
+            tags.known
+                .iter()
+                .filter(|tag| !tags.tags.contains(tag))
+                .map(|tag| {
+                    tag_chip(
+                        format!("+ {}", tag),
+                        false,
+                        Some(on_input(InputChange::AddTags(tag.clone()))),
+                    )
+                }),
+        )
+        .wrap()
+        .vertical_spacing(5);
+
+    let fields = inputs.fields.iter().enumerate().fold(
+        Column::new().spacing(10),
+        |fields, (index, field)| {
+            let on_input_name = on_input.clone();
+            let on_input_kind = on_input.clone();
+            let on_input_value = on_input.clone();
+            fields.push(
+                row![
+                    text_input("Name", &field.name)
+                        .style(invalid_style(field.name.trim().is_empty()))
+                        .on_input(move |value| on_input_name(InputChange::Field(
+                            FieldChange::Name(index, value)
+                        )))
+                        .width(FillPortion(2)),
+                    pick_list(FieldKind::ALL, Some(field.kind), move |kind| {
+                        on_input_kind(InputChange::Field(FieldChange::Kind(index, kind)))
+                    })
+                    .width(FillPortion(2)),
+                    text_input(field.kind.placeholder(), &field.value)
+                        .secure(field.kind == FieldKind::Secret)
+                        .style(invalid_style(!field.kind.is_valid(&field.value)))
+                        .on_input(move |value| {
+                            on_input_value(InputChange::Field(FieldChange::Value(index, value)))
+                        })
+                        .width(FillPortion(3)),
+                    button(text("Remove"))
+                        .on_press(on_input(InputChange::Field(FieldChange::Remove(index)))),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            )
+        },
+    );
 
     column![
         text("Add an entry below").size(16),
         row![
             container(text("Key").size(16).width(label_width).align_y(Center)).padding(5),
-            text_input("Key", key_input)
-                .style(move |theme: &Theme, status| {
-                    let mut style = text_input::default(theme, status);
-                    if is_key_invalid {
-                        style.border = Border {
-                            color: theme.palette().danger,
-                            width: 1.0,
-                            ..Default::default()
-                        };
-                    }
-                    style
-                })
+            text_input("Key", inputs.key)
+                .style(invalid_style(is_key_invalid))
                 .on_input(move |value| on_input_key(InputChange::Key(value)))
         ]
         .spacing(10),
@@ -52,22 +136,55 @@ where
                     .align_y(Center)
             )
             .padding(5),
-            text_input("Description", description_input)
-                .style(move |theme: &Theme, status| {
-                    let mut style = text_input::default(theme, status);
-                    if is_description_invalid {
-                        style.border = Border {
-                            color: theme.palette().danger,
-                            width: 1.0,
-                            ..Default::default()
-                        };
-                    }
-                    style
-                })
-                .on_input(move |value| on_input_description(InputChange::Description(value)))
+            text_input("Description", inputs.description)
+                .style(invalid_style(is_description_invalid))
+                .on_input(move |value| on_input_description(InputChange::Description(value))),
+            button(text("Generate")).on_press(on_input(InputChange::Generate))
+        ]
+        .spacing(10),
+        row![
+            container(text("Tags").size(16).width(label_width).align_y(Center)).padding(5),
+            column![
+                row![
+                    text_input("Add tags, separated by commas", tags.input)
+                        .on_input(move |value| on_input_tag(InputChange::TagInput(value)))
+                        .on_submit(on_input(InputChange::AddTags(tags.input.to_string()))),
+                    button(text("Add"))
+                        .on_press(on_input(InputChange::AddTags(tags.input.to_string()))),
+                ]
+                .spacing(10),
+                tag_chips,
+            ]
+            .spacing(10)
+        ]
+        .spacing(10),
+        row![
+            container(text("Fields").size(16).width(label_width).align_y(Center)).padding(5),
+            column![
+                fields,
+                button(text("Add field")).on_press(on_input(InputChange::Field(FieldChange::Add))),
+            ]
+            .spacing(10)
         ]
         .spacing(10)
     ]
     .spacing(15)
     .into()
 }
+
+/// The default text input, with a danger colored border when `is_invalid`.
+fn invalid_style(
+    is_invalid: bool,
+) -> impl Fn(&Theme, text_input::Status) -> text_input::Style + 'static {
+    move |theme: &Theme, status| {
+        let mut style = text_input::default(theme, status);
+        if is_invalid {
+            style.border = Border {
+                color: theme.palette().danger,
+                width: 1.0,
+                ..Default::default()
+            };
+        }
+        style
+    }
+}
@@ -1,45 +1,164 @@
 use iced::Alignment::Center;
 use iced::Length::{FillPortion, Shrink};
 use iced::widget::space::horizontal;
-use iced::widget::{container, row, text};
+use std::rc::Rc;
+
+use iced::widget::{button, column, container, row, text, tooltip};
 use iced::{Element, Theme};
-use iced_fonts::lucide::{delete, pen};
+use iced_fonts::lucide::{copy, delete, folder_input, history, pen, share};
 
-use crate::base_description_component::{DescriptionElement, description_component};
+use crate::base_description_component::{
+    DescriptionElement, DescriptionSecret, SECRET_MASK, description_component,
+};
 use crate::custom_button_component::custom_button;
 use crate::divider_component::divider;
-use crate::utils::DataEntry;
+use crate::tag_component::tag_chip;
+use crate::utils::{DataEntry, format_timestamp};
+
+/// Messages produced by the buttons of an entry row.
+pub struct EntryActions<Message> {
+    pub on_delete: Message,
+    pub on_edit: Message,
+    pub on_history: Message,
+    pub on_move: Message,
+    pub on_share: Message,
+    pub on_copy_key: Message,
+    pub on_copy_description: Message,
+}
 
 pub fn entry<'a, Message>(
-    entry: &DataEntry,
-    on_delete: Message,
-    on_edit: Message,
+    entry: &'a DataEntry,
+    actions: EntryActions<Message>,
     on_click: impl Fn(DescriptionElement) -> Message + 'a,
+    on_tag: impl Fn(String) -> Message + 'a,
+    revealed: Option<&'a DescriptionSecret>,
+    unix_time: u64,
     theme: &Theme,
 ) -> Element<'a, Message>
 where
     Message: Clone + 'a,
 {
+    let on_click = Rc::new(on_click);
+    let link_color = theme.palette().primary;
+    let fields = entry
+        .fields
+        .iter()
+        .fold(column![].spacing(2), |fields, field| {
+            let element = field.as_element();
+            let value: Element<'a, Message> = match &element {
+                DescriptionElement::Secret(secret) => button(
+                    text(if revealed == Some(secret) {
+                        secret.secret.to_string()
+                    } else {
+                        SECRET_MASK.to_string()
+                    })
+                    .size(14),
+                )
+                .padding(0)
+                .style(button::text)
+                .on_press(on_click(element.clone()))
+                .into(),
+                DescriptionElement::Link(link) => {
+                    button(text(link.text.clone()).size(14).color(link_color))
+                        .padding(0)
+                        .style(button::text)
+                        .on_press(on_click(element.clone()))
+                        .into()
+                }
+                _ => text(field.value.as_str()).size(14).into(),
+            };
+            fields.push(row![text(format!("{}:", field.name)).size(14), value].spacing(5))
+        });
+
+    let tags = entry
+        .tags
+        .iter()
+        .fold(row![].spacing(5), |tags, tag| {
+            tags.push(tag_chip(tag.clone(), false, Some(on_tag(tag.clone()))))
+        })
+        .wrap()
+        .vertical_spacing(5);
+
     container(
         row![
-            container(text(entry.key.clone())).width(FillPortion(4)),
+            container(
+                column![
+                    row![
+                        tooltip(
+                            text(entry.key.as_str()),
+                            container(
+                                text(format!(
+                                    "Folder: {}\nCreated: {}\nModified: {}",
+                                    if entry.folder.is_empty() {
+                                        "none"
+                                    } else {
+                                        &entry.folder
+                                    },
+                                    timestamp(entry.created),
+                                    timestamp(entry.modified)
+                                ))
+                                .size(12)
+                            )
+                            .padding(4)
+                            .style(container::rounded_box),
+                            tooltip::Position::Bottom,
+                        ),
+                        horizontal(),
+                        copy_button("Copy key", actions.on_copy_key),
+                    ]
+                    .spacing(5)
+                    .align_y(Center),
+                    tags,
+                ]
+                .spacing(5)
+            )
+            .width(FillPortion(4)),
             divider(1),
-            container(description_component(
-                entry.description.clone(),
-                on_click,
-                theme
-            ))
+            container(
+                column![
+                    description_component(
+                        &entry.description,
+                        {
+                            let on_click = on_click.clone();
+                            move |element| on_click(element)
+                        },
+                        revealed,
+                        unix_time,
+                        theme
+                    ),
+                    fields,
+                ]
+                .spacing(5)
+            )
             .width(FillPortion(8)),
             divider(1),
             container(
                 row![
-                    custom_button(pen(), on_edit, |theme: &Theme| theme
+                    copy_button("Copy description", actions.on_copy_description),
+                    custom_button(share(), actions.on_share, |theme: &Theme| theme
+                        .extended_palette()
+                        .secondary
+                        .base
+                        .color),
+                    custom_button(pen(), actions.on_edit, |theme: &Theme| theme
+                        .extended_palette()
+                        .secondary
+                        .base
+                        .color),
+                    custom_button(history(), actions.on_history, |theme: &Theme| theme
+                        .extended_palette()
+                        .secondary
+                        .base
+                        .color),
+                    custom_button(folder_input(), actions.on_move, |theme: &Theme| theme
                         .extended_palette()
                         .secondary
                         .base
                         .color),
                     horizontal(),
-                    custom_button(delete(), on_delete, |theme: &Theme| theme.palette().danger),
+                    custom_button(delete(), actions.on_delete, |theme: &Theme| theme
+                        .palette()
+                        .danger),
                 ]
                 .spacing(10)
             )
@@ -60,3 +179,26 @@ where
     .padding(5)
     .into()
 }
+
+fn copy_button<'a, Message>(label: &'a str, on_copy: Message) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    tooltip(
+        custom_button(copy(), on_copy, |theme: &Theme| {
+            theme.extended_palette().background.strong.color
+        }),
+        container(text(label).size(12))
+            .padding(4)
+            .style(container::rounded_box),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
+
+fn timestamp(unix_time: u64) -> String {
+    match unix_time {
+        0 => "unknown".to_string(),
+        unix_time => format!("{} UTC", format_timestamp(unix_time)),
+    }
+}
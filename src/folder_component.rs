@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use iced::Alignment::Center;
+use iced::Length::Fill;
+use iced::widget::space::horizontal;
+use iced::widget::{Column, button, column, row, text};
+use iced::{Element, Theme};
+use iced_fonts::lucide::{chevron_down, chevron_right, folder, folder_open};
+
+#[derive(Debug, Clone)]
+pub enum FolderChange {
+    /// `None` shows the entries of every folder.
+    Select(Option<String>),
+    Toggle(String),
+}
+
+/// The folders as a tree, `folders` come from `AppData::folders`. Subfolders are only shown
+/// when their parent is in `expanded`.
+pub fn folder_tree<'a, Message>(
+    folders: &[(String, usize)],
+    entry_count: usize,
+    selected: Option<&str>,
+    expanded: &HashSet<String>,
+    on_change: impl Fn(FolderChange) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let mut tree: Column<'a, Message> = column![folder_row(
+        text("All entries").size(14).into(),
+        entry_count,
+        selected.is_none(),
+        on_change(FolderChange::Select(None)),
+    )]
+    .spacing(2);
+
+    for (index, (path, count)) in folders.iter().enumerate() {
+        let is_visible = path
+            .match_indices('/')
+            .all(|(end, _)| expanded.contains(&path[..end]));
+        if !is_visible {
+            continue;
+        }
+
+        let depth = path.matches('/').count();
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let has_children = folders
+            .get(index + 1)
+            .is_some_and(|(next, _)| next.starts_with(&format!("{}/", path)));
+        let is_expanded = expanded.contains(path);
+
+        let toggle: Element<'a, Message> = if has_children {
+            button(if is_expanded {
+                chevron_down().size(12)
+            } else {
+                chevron_right().size(12)
+            })
+            .padding(2)
+            .style(button::text)
+            .on_press(on_change(FolderChange::Toggle(path.clone())))
+            .into()
+        } else {
+            horizontal().width(16).into()
+        };
+
+        tree = tree.push(
+            row![
+                horizontal().width(depth as f32 * 14.0),
+                toggle,
+                folder_row(
+                    row![
+                        if is_expanded {
+                            folder_open().size(14)
+                        } else {
+                            folder().size(14)
+                        },
+                        text(name.to_string()).size(14),
+                    ]
+                    .spacing(5)
+                    .into(),
+                    *count,
+                    selected == Some(path.as_str()),
+                    on_change(FolderChange::Select(Some(path.clone()))),
+                ),
+            ]
+            .align_y(Center),
+        );
+    }
+
+    tree.width(Fill).into()
+}
+
+fn folder_row<'a, Message>(
+    label: Element<'a, Message>,
+    count: usize,
+    is_selected: bool,
+    on_press: Message,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    button(
+        row![label, horizontal(), text(count.to_string()).size(12)]
+            .spacing(5)
+            .align_y(Center),
+    )
+    .width(Fill)
+    .padding([3, 6])
+    .style(move |theme: &Theme, status| {
+        if is_selected {
+            button::primary(theme, status)
+        } else {
+            button::text(theme, status)
+        }
+    })
+    .on_press(on_press)
+    .into()
+}
@@ -0,0 +1,166 @@
+use std::rc::Rc;
+
+use iced::Alignment::Center;
+use iced::Length::{self, Fill};
+use iced::widget::{
+    Column, button, checkbox, column, container, pick_list, row, slider, text, text_input,
+};
+use iced::{Element, Font};
+
+use crate::password_generator::{
+    GeneratorMode, GeneratorOptions, PASSPHRASE_WORDS_RANGE, PASSWORD_LENGTH_RANGE, strength_label,
+};
+
+#[derive(Debug, Clone)]
+pub enum GeneratorChange {
+    Mode(GeneratorMode),
+    Length(u8),
+    Lowercase(bool),
+    Uppercase(bool),
+    Digits(bool),
+    Symbols(bool),
+    ExcludeAmbiguous(bool),
+    Words(u8),
+    Separator(String),
+    Capitalize(bool),
+    Regenerate,
+}
+
+type ToggleChange = fn(bool) -> GeneratorChange;
+
+pub fn generator<'a, Message>(
+    label_width: Length,
+    options: &GeneratorOptions,
+    generated: &str,
+    on_change: impl Fn(GeneratorChange) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let on_change = Rc::new(on_change);
+    let on_change_mode = on_change.clone();
+
+    let mut content: Column<'a, Message> = column![
+        row![
+            label("Type", label_width),
+            pick_list(GeneratorMode::ALL, Some(options.mode), move |value| {
+                on_change_mode(GeneratorChange::Mode(value))
+            })
+            .width(Fill)
+        ]
+        .spacing(10),
+    ]
+    .spacing(15);
+
+    match options.mode {
+        GeneratorMode::Password => {
+            let on_change_length = on_change.clone();
+            content = content.push(
+                row![
+                    label("Length", label_width),
+                    slider(PASSWORD_LENGTH_RANGE, options.length, move |value| {
+                        on_change_length(GeneratorChange::Length(value))
+                    }),
+                    text(options.length.to_string()).size(16).width(30),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            );
+
+            let toggles: [(&str, bool, ToggleChange); 5] = [
+                (
+                    "Lowercase a-z",
+                    options.lowercase,
+                    GeneratorChange::Lowercase,
+                ),
+                (
+                    "Uppercase A-Z",
+                    options.uppercase,
+                    GeneratorChange::Uppercase,
+                ),
+                ("Digits 0-9", options.digits, GeneratorChange::Digits),
+                ("Symbols !#$%", options.symbols, GeneratorChange::Symbols),
+                (
+                    "Exclude ambiguous (I l 1 | O 0 o)",
+                    options.exclude_ambiguous,
+                    GeneratorChange::ExcludeAmbiguous,
+                ),
+            ];
+            for (name, is_checked, change) in toggles {
+                let on_change_toggle = on_change.clone();
+                content = content.push(
+                    checkbox(is_checked)
+                        .label(name)
+                        .on_toggle(move |value| on_change_toggle(change(value))),
+                );
+            }
+        }
+        GeneratorMode::Passphrase => {
+            let on_change_words = on_change.clone();
+            let on_change_separator = on_change.clone();
+            let on_change_capitalize = on_change.clone();
+            content = content
+                .push(
+                    row![
+                        label("Words", label_width),
+                        slider(PASSPHRASE_WORDS_RANGE, options.words, move |value| {
+                            on_change_words(GeneratorChange::Words(value))
+                        }),
+                        text(options.words.to_string()).size(16).width(30),
+                    ]
+                    .spacing(10)
+                    .align_y(Center),
+                )
+                .push(
+                    row![
+                        label("Separator", label_width),
+                        text_input("Separator", &options.separator).on_input(move |value| {
+                            on_change_separator(GeneratorChange::Separator(value))
+                        })
+                    ]
+                    .spacing(10),
+                )
+                .push(
+                    checkbox(options.capitalize)
+                        .label("Capitalize words")
+                        .on_toggle(move |value| {
+                            on_change_capitalize(GeneratorChange::Capitalize(value))
+                        }),
+                );
+        }
+    }
+
+    let bits = options.entropy_bits();
+    let result = if generated.is_empty() {
+        "Select at least one character set".to_string()
+    } else {
+        generated.to_string()
+    };
+
+    content
+        .push(
+            row![
+                container(text(result).size(16).font(Font::MONOSPACE))
+                    .width(Fill)
+                    .padding(5),
+                button(text("Regenerate")).on_press(on_change(GeneratorChange::Regenerate)),
+            ]
+            .spacing(10)
+            .align_y(Center),
+        )
+        .push(
+            text(format!(
+                "Entropy ≈ {:.0} bits, {}",
+                bits,
+                strength_label(bits)
+            ))
+            .size(14),
+        )
+        .into()
+}
+
+fn label<'a, Message: 'a>(value: &'a str, label_width: Length) -> Element<'a, Message> {
+    container(text(value).size(16).width(label_width).align_y(Center))
+        .padding(5)
+        .into()
+}
@@ -0,0 +1,100 @@
+use iced::Alignment::Center;
+use iced::Length::Fill;
+use iced::widget::text::Span;
+use iced::widget::{Column, button, column, container, rich_text, row, scrollable, span, text};
+use iced::{Element, Theme};
+
+use crate::base_description_component::plain_description;
+use crate::diff::{DiffPart, word_diff};
+use crate::utils::{DataEntry, HISTORY_LIMIT, format_timestamp};
+
+/// The earlier versions of `entry`, newest first, each with the words that changed since.
+pub fn history<'a, Message>(
+    entry: &'a DataEntry,
+    on_revert: impl Fn(usize) -> Message + 'a,
+    theme: &Theme,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    if entry.history.is_empty() {
+        return text(format!(
+            "\"{}\" has no earlier versions, up to {} are kept every time it is edited.",
+            entry.key.as_str(),
+            HISTORY_LIMIT
+        ))
+        .size(16)
+        .into();
+    }
+
+    let current = plain_description(&entry.description);
+    let list = entry.history.iter().enumerate().rev().fold(
+        column![].spacing(15),
+        |list, (index, revision)| {
+            let mut version: Column<'a, Message> = column![
+                row![
+                    text(match revision.modified {
+                        0 => "Saved at an unknown time".to_string(),
+                        modified => format!("Saved {} UTC", format_timestamp(modified)),
+                    })
+                    .size(16)
+                    .width(Fill),
+                    button(text("Revert to this version")).on_press(on_revert(index)),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            ]
+            .spacing(5);
+
+            if revision.key != entry.key {
+                version = version.push(
+                    text(format!(
+                        "Key: {} → {}",
+                        revision.key.as_str(),
+                        entry.key.as_str()
+                    ))
+                    .size(14),
+                );
+            }
+            version = version.push(diff_text(
+                &plain_description(&revision.description),
+                &current,
+                theme,
+            ));
+
+            list.push(container(version).padding(5).style(container::bordered_box))
+        },
+    );
+
+    column![
+        text("Changes from each version to the current one, removed words are struck through.")
+            .size(14),
+        scrollable(list).height(350),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn diff_text<'a, Message>(old: &str, new: &str, theme: &Theme) -> Element<'a, Message>
+where
+    Message: 'a,
+{
+    let palette = theme.palette();
+    let spans: Vec<Span<'a, ()>> = word_diff(old, new)
+        .into_iter()
+        .map(|part| match part {
+            DiffPart::Same(words) => span(format!("{} ", words)),
+            DiffPart::Added(words) => span(format!("{} ", words))
+                .color(palette.success)
+                .underline(true),
+            DiffPart::Removed(words) => span(format!("{} ", words))
+                .color(palette.danger)
+                .strikethrough(true),
+        })
+        .collect();
+
+    rich_text(spans)
+        .size(14)
+        .wrapping(iced::widget::text::Wrapping::WordOrGlyph)
+        .into()
+}
@@ -0,0 +1,622 @@
+use std::fmt;
+use std::io;
+
+use serde::Deserialize;
+
+use crate::base_description_component::{DescriptionElement, DescriptionSecret, Link};
+use crate::csv_import::{SkippedRow, parse_csv, single_line};
+use crate::secret_string::SecretString;
+use crate::utils::DataEntry;
+
+/// Exports of other password managers that can be read into entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    KeePassXml,
+    BitwardenJson,
+    BrowserCsv,
+}
+
+impl ImportSource {
+    pub const ALL: [ImportSource; 3] = [
+        ImportSource::KeePassXml,
+        ImportSource::BitwardenJson,
+        ImportSource::BrowserCsv,
+    ];
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ImportSource::KeePassXml => &["xml"],
+            ImportSource::BitwardenJson => &["json"],
+            ImportSource::BrowserCsv => &["csv"],
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ImportSource::KeePassXml => {
+                "KeePass 2 XML export, made with File > Export > KeePass XML (2.x)."
+            }
+            ImportSource::BitwardenJson => {
+                "Bitwarden export in the .json format, encrypted exports can not be read."
+            }
+            ImportSource::BrowserCsv => {
+                "Passwords exported as CSV by Chrome, Edge, Firefox or Safari."
+            }
+        }
+    }
+
+    /// Reads `contents` as this kind of export, credentials that cannot be stored as they are
+    /// are returned with the reason.
+    pub fn import(&self, contents: &str) -> io::Result<(Vec<DataEntry>, Vec<SkippedRow>)> {
+        match self {
+            ImportSource::KeePassXml => keepass_xml(contents),
+            ImportSource::BitwardenJson => bitwarden_json(contents),
+            ImportSource::BrowserCsv => Ok(browser_csv(contents)),
+        }
+    }
+}
+
+impl fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportSource::KeePassXml => write!(f, "KeePass XML"),
+            ImportSource::BitwardenJson => write!(f, "Bitwarden JSON"),
+            ImportSource::BrowserCsv => write!(f, "Browser CSV"),
+        }
+    }
+}
+
+/// The fields password managers have in common, turned into an entry by `into_entry`.
+#[derive(Default)]
+struct Credential {
+    /// Where the credential is in the export, like `Line 4`.
+    location: String,
+    title: String,
+    username: String,
+    password: SecretString,
+    urls: Vec<String>,
+    notes: SecretString,
+    /// Extra named values, secret ones become secret elements.
+    fields: Vec<(String, SecretString, bool)>,
+}
+
+impl Credential {
+    /// Entries are stored one per line, so every value is put on one line before it is used.
+    /// Secrets are kept as they are, `into_entry` skips the ones with line breaks.
+    fn single_line(self) -> Self {
+        Credential {
+            location: self.location,
+            title: single_line(&self.title).to_string(),
+            username: single_line(&self.username).to_string(),
+            password: self.password,
+            urls: self
+                .urls
+                .iter()
+                .map(|url| single_line(url).to_string())
+                .collect(),
+            notes: single_line(&self.notes),
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(name, value, is_secret)| {
+                    let value = if is_secret {
+                        value
+                    } else {
+                        single_line(&value)
+                    };
+                    (single_line(&name).to_string(), value, is_secret)
+                })
+                .collect(),
+        }
+    }
+
+    /// `None` when there is nothing to import. A secret with a line break can not be stored
+    /// without changing it, so the credential is skipped instead.
+    fn into_entry(self) -> Result<Option<DataEntry>, SkippedRow> {
+        let has_line_break = |value: &str| value.contains(['\r', '\n']);
+        if has_line_break(&self.password)
+            || self
+                .fields
+                .iter()
+                .any(|(_, value, is_secret)| *is_secret && has_line_break(value))
+        {
+            return Err(SkippedRow {
+                location: self.location,
+                reason: "a secret has a line break, which entries can not hold".to_string(),
+            });
+        }
+
+        let credential = self.single_line();
+        let key = match credential.title.trim() {
+            "" => match credential
+                .urls
+                .first()
+                .map(|url| link_label(url))
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| credential.username.trim().to_string())
+            {
+                key if key.is_empty() => return Ok(None),
+                key => key,
+            },
+            title => title.to_string(),
+        };
+
+        let mut description = Vec::new();
+        let mut push = |element: DescriptionElement| {
+            if !description.is_empty() {
+                description.push(DescriptionElement::Text(" ".into()));
+            }
+            description.push(element);
+        };
+
+        if !credential.username.trim().is_empty() {
+            push(DescriptionElement::Text(
+                format!("User: {}", credential.username.trim()).into(),
+            ));
+        }
+        if !credential.password.is_empty() {
+            push(secret("Password", credential.password));
+        }
+        for url in credential.urls.iter().filter(|url| !url.trim().is_empty()) {
+            push(DescriptionElement::Link(Link {
+                text: link_label(url),
+                link: url.trim().to_string(),
+            }));
+        }
+        for (name, value, is_secret) in credential.fields {
+            if value.is_empty() {
+                continue;
+            }
+            push(if is_secret {
+                secret(&name, value)
+            } else {
+                DescriptionElement::Text(format!("{}: {}", name, value.as_str()).into())
+            });
+        }
+        if !credential.notes.trim().is_empty() {
+            push(DescriptionElement::Text(credential.notes.trim().into()));
+        }
+
+        Ok(Some(DataEntry::with_description(&key, description)))
+    }
+}
+
+/// Splits converted credentials into the entries and the skipped ones.
+fn collect_entries(
+    credentials: impl Iterator<Item = Result<Option<DataEntry>, SkippedRow>>,
+) -> (Vec<DataEntry>, Vec<SkippedRow>) {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for credential in credentials {
+        match credential {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(row) => skipped.push(row),
+        }
+    }
+    (entries, skipped)
+}
+
+fn secret(label: &str, value: SecretString) -> DescriptionElement {
+    DescriptionElement::Secret(DescriptionSecret {
+        text: label.to_string(),
+        secret: value,
+    })
+}
+
+/// Host of `url` used as the link text, `https://mail.example.com/login` shows as
+/// `mail.example.com`.
+fn link_label(url: &str) -> String {
+    let url = url.trim();
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    if host.is_empty() {
+        url.to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+/// KeePass keeps every field as a `String` with a `Key`, the ones besides the standard five
+/// become extra fields.
+fn keepass_xml(contents: &str) -> io::Result<(Vec<DataEntry>, Vec<SkippedRow>)> {
+    let document = roxmltree::Document::parse(contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let root = document.root_element();
+    if !root.has_tag_name("KeePassFile") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a KeePass XML export",
+        ));
+    }
+
+    let child_text = |node: roxmltree::Node, name: &str| -> Option<String> {
+        node.children()
+            .find(|child| child.has_tag_name(name))
+            .and_then(|child| child.text())
+            .map(str::to_string)
+    };
+
+    let recycle_bin = root
+        .descendants()
+        .find(|node| node.has_tag_name("RecycleBinUUID"))
+        .and_then(|node| node.text())
+        .filter(|uuid| uuid.trim_matches('A') != "==");
+
+    let entries = root
+        .descendants()
+        .filter(|node| node.has_tag_name("Entry"))
+        // Old versions of an entry are kept inside it under `History`.
+        .filter(|node| {
+            !node
+                .ancestors()
+                .any(|ancestor| ancestor.has_tag_name("History"))
+        })
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| {
+                ancestor.has_tag_name("Group")
+                    && recycle_bin.is_some()
+                    && child_text(ancestor, "UUID").as_deref() == recycle_bin
+            })
+        })
+        .map(|node| {
+            let mut credential = Credential {
+                location: format!("Line {}", document.text_pos_at(node.range().start).row),
+                ..Credential::default()
+            };
+            for string in node.children().filter(|child| child.has_tag_name("String")) {
+                let Some(key) = child_text(string, "Key") else {
+                    continue;
+                };
+                let value_node = string.children().find(|child| child.has_tag_name("Value"));
+                let value = value_node
+                    .and_then(|value| value.text())
+                    .unwrap_or_default()
+                    .to_string();
+                let is_protected = value_node.and_then(|value| value.attribute("ProtectInMemory"))
+                    == Some("True")
+                    || value_node.and_then(|value| value.attribute("Protected")) == Some("True");
+
+                match key.as_str() {
+                    "Title" => credential.title = value,
+                    "UserName" => credential.username = value,
+                    "Password" => credential.password = value.into(),
+                    "URL" => credential.urls.push(value),
+                    "Notes" => credential.notes = value.into(),
+                    _ => credential.fields.push((key, value.into(), is_protected)),
+                }
+            }
+            credential.into_entry()
+        });
+
+    Ok(collect_entries(entries))
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenItem {
+    #[serde(default)]
+    name: String,
+    notes: Option<SecretString>,
+    #[serde(default)]
+    fields: Vec<BitwardenField>,
+    login: Option<BitwardenLogin>,
+    card: Option<BitwardenCard>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<SecretString>,
+    /// 0 text, 1 hidden, 2 boolean, 3 linked.
+    #[serde(rename = "type", default)]
+    field_type: u8,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<SecretString>,
+    totp: Option<SecretString>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenCard {
+    cardholder_name: Option<String>,
+    number: Option<SecretString>,
+    exp_month: Option<String>,
+    exp_year: Option<String>,
+    code: Option<SecretString>,
+}
+
+fn bitwarden_json(contents: &str) -> io::Result<(Vec<DataEntry>, Vec<SkippedRow>)> {
+    let export: BitwardenExport = serde_json::from_str(contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if export.encrypted {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Encrypted Bitwarden exports can not be read, export as unencrypted .json",
+        ));
+    }
+
+    Ok(collect_entries(export.items.into_iter().enumerate().map(
+        |(index, item)| {
+            let mut credential = Credential {
+                location: format!("Item {}", index + 1),
+                title: item.name,
+                notes: item.notes.unwrap_or_default(),
+                ..Credential::default()
+            };
+
+            if let Some(login) = item.login {
+                credential.username = login.username.unwrap_or_default();
+                credential.password = login.password.unwrap_or_default();
+                credential.urls = login.uris.into_iter().filter_map(|uri| uri.uri).collect();
+                if let Some(totp) = login.totp {
+                    credential.fields.push(("TOTP".to_string(), totp, true));
+                }
+            }
+            if let Some(card) = item.card {
+                if let Some(holder) = card.cardholder_name {
+                    credential
+                        .fields
+                        .push(("Cardholder".to_string(), holder.into(), false));
+                }
+                if let Some(number) = card.number {
+                    credential
+                        .fields
+                        .push(("Card number".to_string(), number, true));
+                }
+                if let (Some(month), Some(year)) = (card.exp_month, card.exp_year) {
+                    credential.fields.push((
+                        "Expires".to_string(),
+                        format!("{}/{}", month, year).into(),
+                        false,
+                    ));
+                }
+                if let Some(code) = card.code {
+                    credential
+                        .fields
+                        .push(("Security code".to_string(), code, true));
+                }
+            }
+            for field in item.fields {
+                credential.fields.push((
+                    field.name.unwrap_or_else(|| "Field".to_string()),
+                    field.value.unwrap_or_default(),
+                    field.field_type == 1,
+                ));
+            }
+
+            credential.into_entry()
+        },
+    )))
+}
+
+/// Browsers name their columns differently, the first matching name of each list is used.
+const BROWSER_TITLE_COLUMNS: [&str; 2] = ["name", "title"];
+const BROWSER_URL_COLUMNS: [&str; 3] = ["url", "login_uri", "origin"];
+const BROWSER_USERNAME_COLUMNS: [&str; 2] = ["username", "login_username"];
+const BROWSER_PASSWORD_COLUMNS: [&str; 2] = ["password", "login_password"];
+const BROWSER_NOTES_COLUMNS: [&str; 3] = ["note", "notes", "extra"];
+const BROWSER_TOTP_COLUMNS: [&str; 2] = ["otpauth", "login_totp"];
+
+fn browser_csv(contents: &str) -> (Vec<DataEntry>, Vec<SkippedRow>) {
+    let records = parse_csv(contents);
+    let Some((header, rows)) = records.split_first() else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let column = |names: &[&str]| {
+        header
+            .fields
+            .iter()
+            .position(|name| names.contains(&name.trim().to_lowercase().as_str()))
+    };
+    let title = column(&BROWSER_TITLE_COLUMNS);
+    let url = column(&BROWSER_URL_COLUMNS);
+    let username = column(&BROWSER_USERNAME_COLUMNS);
+    let password = column(&BROWSER_PASSWORD_COLUMNS);
+    let notes = column(&BROWSER_NOTES_COLUMNS);
+    let totp = column(&BROWSER_TOTP_COLUMNS);
+
+    collect_entries(rows.iter().map(|row| {
+        let value = |index: Option<usize>| -> SecretString {
+            index
+                .and_then(|index| row.fields.get(index))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let mut credential = Credential {
+            location: format!("Line {}", row.line),
+            title: value(title).to_string(),
+            username: value(username).to_string(),
+            password: value(password),
+            urls: vec![value(url).to_string()],
+            notes: value(notes),
+            fields: Vec::new(),
+        };
+        let totp = value(totp);
+        if !totp.is_empty() {
+            credential.fields.push(("TOTP".to_string(), totp, true));
+        }
+        credential.into_entry()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each entry as the line it is saved as, read back so markup escaping is covered too.
+    fn saved_lines(entries: &[DataEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| {
+                let line = entry.to_line();
+                assert!(!line.contains(['\r', '\n']), "{}", line.as_str());
+                assert!(!entry.key.contains(['\r', '\n']));
+                let reloaded = DataEntry::from_line(&line).unwrap();
+                assert_eq!(reloaded.to_line(), line);
+                line.to_string()
+            })
+            .collect()
+    }
+
+    fn find_secret(entry: &DataEntry, label: &str) -> Option<String> {
+        entry.description.iter().find_map(|element| match element {
+            DescriptionElement::Secret(secret) if secret.text == label => {
+                Some(secret.secret.to_string())
+            }
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn keepass_xml_fixture() {
+        let (entries, skipped) = ImportSource::KeePassXml
+            .import(include_str!("../tests/fixtures/keepass.xml"))
+            .unwrap();
+        assert!(skipped.is_empty());
+
+        // The history copy and the recycle bin entry are left out.
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key.as_str(), "Office Mail");
+        assert_eq!(
+            find_secret(&entries[0], "Password").unwrap(),
+            "p\"a:ss&word"
+        );
+        assert_eq!(find_secret(&entries[0], "PIN").unwrap(), "4321");
+        assert!(matches!(
+            &entries[0].description[4],
+            DescriptionElement::Link(link)
+                if link.link == "https://mail.example.com/owa" && link.text == "mail.example.com"
+        ));
+        assert_eq!(
+            saved_lines(&entries)[1],
+            r#"Server Room:User\: root <secret="hunter2" text="Password"> Rack\: B2 Door code changes monthly"#
+        );
+    }
+
+    #[test]
+    fn bitwarden_json_fixture() {
+        let (entries, skipped) = ImportSource::BitwardenJson
+            .import(include_str!("../tests/fixtures/bitwarden.json"))
+            .unwrap();
+        assert!(skipped.is_empty());
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].key.as_str(), "Home Router");
+        assert_eq!(find_secret(&entries[0], "Password").unwrap(), "admin123");
+        assert_eq!(
+            find_secret(&entries[0], "TOTP").unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+        assert_eq!(
+            find_secret(&entries[0], "Wifi key").unwrap(),
+            "correct horse"
+        );
+        assert_eq!(
+            find_secret(&entries[1], "Card number").unwrap(),
+            "4111111111111111"
+        );
+        assert_eq!(entries[2].key.as_str(), "Alarm codes");
+        saved_lines(&entries);
+
+        assert!(
+            ImportSource::BitwardenJson
+                .import(r#"{"encrypted": true, "items": []}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn browser_csv_fixtures() {
+        let (chrome, skipped) = ImportSource::BrowserCsv
+            .import(include_str!("../tests/fixtures/chrome.csv"))
+            .unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(chrome.len(), 2);
+        assert_eq!(chrome[0].key.as_str(), "github.com");
+        assert_eq!(find_secret(&chrome[0], "Password").unwrap(), "pa,ss\"word");
+        assert_eq!(
+            saved_lines(&chrome)[1],
+            r#"intranet:User\: jdoe <secret="s3cret" text="Password"> <link="http\://intranet/login" text="intranet"> VPN only, ask IT"#
+        );
+
+        // Firefox has no title column, the host is used instead.
+        let (firefox, skipped) = ImportSource::BrowserCsv
+            .import(include_str!("../tests/fixtures/firefox.csv"))
+            .unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(firefox.len(), 1);
+        assert_eq!(firefox[0].key.as_str(), "accounts.example.org");
+        assert_eq!(find_secret(&firefox[0], "Password").unwrap(), "ff-pass");
+    }
+
+    #[test]
+    fn multi_line_values_are_put_on_one_line() {
+        let (entries, skipped) = ImportSource::KeePassXml
+            .import(include_str!("../tests/fixtures/keepass_multiline.xml"))
+            .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        // Without a title or URL the key is the user name.
+        assert_eq!(entries[0].key.as_str(), "jdoe backup");
+        // Without a title the key is the host of the first URL.
+        assert_eq!(entries[1].key.as_str(), "vpn.example.com");
+        assert!(entries[1].description.iter().any(|element| matches!(
+            element,
+            DescriptionElement::Link(link)
+                if link.link == "https://vpn.example.com/login https://backup.example.com"
+        )));
+        assert_eq!(
+            saved_lines(&entries)[1],
+            r#"vpn.example.com:<link="https\://vpn.example.com/login https\://backup.example.com" text="vpn.example.com"> <secret="1111-2222" text="Recovery code"> Room number\: B2"#
+        );
+
+        // A secret is never changed, the entry with a two line password is left out.
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(
+            skipped[0].to_string(),
+            "Line 41: a secret has a line break, which entries can not hold"
+        );
+    }
+
+    #[test]
+    fn secrets_with_line_breaks_are_skipped() {
+        let (entries, skipped) = ImportSource::BrowserCsv
+            .import("name,url,username,password\nMail,,me,\"one\ntwo\"\nBank,,me,pin\n")
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key.as_str(), "Bank");
+        assert_eq!(skipped[0].location, "Line 2");
+
+        let (entries, skipped) = ImportSource::BitwardenJson
+            .import(
+                r#"{"items": [{"name": "Card", "fields": [{"name": "PIN", "value": "12\n34", "type": 1}]}]}"#,
+            )
+            .unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(skipped[0].location, "Item 1");
+    }
+}
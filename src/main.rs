@@ -2,26 +2,44 @@
 
 mod app;
 mod base_description_component;
+mod csv_import;
+mod csv_import_component;
 mod custom_button_component;
+mod custom_field;
+mod diff;
 mod divider_component;
 mod entity_edit_component;
 mod entry_component;
+mod folder_component;
+mod generator_component;
+mod history_component;
+mod importers;
 mod menu_button_component;
+mod password_component;
+mod password_generator;
 mod search_component;
+mod secret_string;
+mod settings;
+mod shamir;
+mod share_component;
+mod tag_component;
 mod theme;
+mod totp;
 mod utils;
+mod vault;
 mod window_component;
 mod window_manager;
 
 use app::App;
 use iced::window::Settings;
 
-use crate::{theme::APP_TITLE, utils::load_icon};
+use crate::utils::load_icon;
 use iced_aw::ICED_AW_FONT_BYTES;
 
 pub fn main() -> iced::Result {
     iced::application(App::new, App::update, App::view)
         .theme(App::theme)
+        .subscription(App::subscription)
         .font(ICED_AW_FONT_BYTES)
         .window(Settings {
             min_size: Some((400, 200).into()),
@@ -37,6 +55,7 @@ pub fn main() -> iced::Result {
             ..Default::default()
         })
         .centered()
-        .title(APP_TITLE)
+        .title(App::title)
+        .exit_on_close_request(false)
         .run()
 }
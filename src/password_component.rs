@@ -0,0 +1,204 @@
+use std::rc::Rc;
+
+use iced::Alignment::Center;
+use iced::Length::{self, Fill};
+use iced::widget::{Column, button, column, container, pick_list, row, text, text_input};
+use iced::{Border, Element, Theme};
+
+use crate::vault::UnlockFactors;
+
+pub enum PasswordChange {
+    Current(String),
+    Password(String),
+    Confirm(String),
+    Memory(String),
+    Iterations(String),
+    Factors(UnlockFactors),
+    BrowseKeyFile,
+    CreateKeyFile,
+}
+
+/// Values shown by `password_form`, a `None` field hides its row.
+#[derive(Default)]
+pub struct PasswordInputs<'a> {
+    pub factors: Option<UnlockFactors>,
+    pub current: Option<&'a str>,
+    pub password: Option<&'a str>,
+    pub confirm: Option<&'a str>,
+    pub key_file: Option<&'a str>,
+    pub memory_mib: Option<&'a str>,
+    pub iterations: Option<&'a str>,
+}
+
+pub fn password_form<'a, Message>(
+    label_width: Length,
+    message: &str,
+    inputs: PasswordInputs<'_>,
+    is_password_invalid: bool,
+    is_cost_invalid: bool,
+    on_input: impl Fn(PasswordChange) -> Message + 'a,
+    on_submit: Message,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let on_input = Rc::new(on_input);
+    let can_create_key_file = inputs.factors.is_some();
+
+    let mut content: Column<'a, Message> = column![text(message.to_string()).size(16)].spacing(15);
+
+    if let Some(factors) = inputs.factors {
+        let on_input_factors = on_input.clone();
+        content = content.push(
+            row![
+                container(
+                    text("Unlock with")
+                        .size(16)
+                        .width(label_width)
+                        .align_y(Center)
+                )
+                .padding(5),
+                pick_list(UnlockFactors::ALL, Some(factors), move |value| {
+                    on_input_factors(PasswordChange::Factors(value))
+                })
+                .width(Fill)
+            ]
+            .spacing(10),
+        );
+    }
+
+    if let Some(current) = inputs.current {
+        let on_input_current = on_input.clone();
+        content = content.push(password_row(
+            label_width,
+            "Current",
+            current,
+            false,
+            move |value| on_input_current(PasswordChange::Current(value)),
+            on_submit.clone(),
+        ));
+    }
+
+    if let Some(password) = inputs.password {
+        let on_input_password = on_input.clone();
+        content = content.push(password_row(
+            label_width,
+            "Password",
+            password,
+            is_password_invalid,
+            move |value| on_input_password(PasswordChange::Password(value)),
+            on_submit.clone(),
+        ));
+    }
+
+    if let Some(confirm) = inputs.confirm {
+        let on_input_confirm = on_input.clone();
+        content = content.push(password_row(
+            label_width,
+            "Confirm",
+            confirm,
+            is_password_invalid,
+            move |value| on_input_confirm(PasswordChange::Confirm(value)),
+            on_submit.clone(),
+        ));
+    }
+
+    if let Some(key_file) = inputs.key_file {
+        let mut key_file_row = row![
+            container(text("Key File").size(16).width(label_width).align_y(Center)).padding(5),
+            container(text(key_file.to_string()).size(14))
+                .width(Fill)
+                .padding(5),
+            button(text("Browse")).on_press(on_input(PasswordChange::BrowseKeyFile)),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
+        if can_create_key_file {
+            key_file_row = key_file_row
+                .push(button(text("Create")).on_press(on_input(PasswordChange::CreateKeyFile)));
+        }
+        content = content.push(key_file_row);
+    }
+
+    if let (Some(memory_mib), Some(iterations)) = (inputs.memory_mib, inputs.iterations) {
+        let on_input_memory = on_input.clone();
+        let on_input_iterations = on_input.clone();
+        content = content
+            .push(
+                text("Key derivation cost, higher values are slower to unlock and to attack.")
+                    .size(14),
+            )
+            .push(cost_row(
+                label_width,
+                "Memory MiB",
+                memory_mib,
+                is_cost_invalid,
+                move |value| on_input_memory(PasswordChange::Memory(value)),
+            ))
+            .push(cost_row(
+                label_width,
+                "Iterations",
+                iterations,
+                is_cost_invalid,
+                move |value| on_input_iterations(PasswordChange::Iterations(value)),
+            ));
+    }
+
+    content.into()
+}
+
+fn cost_row<'a, Message>(
+    label_width: Length,
+    label: &'a str,
+    value: &str,
+    is_invalid: bool,
+    on_input: impl Fn(String) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    row![
+        container(text(label).size(16).width(label_width).align_y(Center)).padding(5),
+        text_input(label, value)
+            .style(move |theme: &Theme, status| invalid_style(theme, status, is_invalid))
+            .on_input(on_input)
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn invalid_style(theme: &Theme, status: text_input::Status, is_invalid: bool) -> text_input::Style {
+    let mut style = text_input::default(theme, status);
+    if is_invalid {
+        style.border = Border {
+            color: theme.palette().danger,
+            width: 1.0,
+            ..Default::default()
+        };
+    }
+    style
+}
+
+fn password_row<'a, Message>(
+    label_width: Length,
+    label: &'a str,
+    value: &str,
+    is_invalid: bool,
+    on_input: impl Fn(String) -> Message + 'a,
+    on_submit: Message,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    row![
+        container(text(label).size(16).width(label_width).align_y(Center)).padding(5),
+        text_input(label, value)
+            .secure(true)
+            .style(move |theme: &Theme, status| invalid_style(theme, status, is_invalid))
+            .on_input(on_input)
+            .on_submit(on_submit)
+    ]
+    .spacing(10)
+    .into()
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+// Quotes, backslashes and colons are left out so a password fits into description markup as is.
+const SYMBOLS: &str = "!#$%&()*+,-./;<=>?@[]^_{|}~";
+const AMBIGUOUS: &str = "Il1|O0o";
+
+/// EFF large wordlist, `NNNNN\tword` per line.
+const WORDLIST: &str = include_str!("../assets/eff_large_wordlist.txt");
+
+pub const PASSWORD_LENGTH_RANGE: std::ops::RangeInclusive<u8> = 8..=64;
+pub const PASSPHRASE_WORDS_RANGE: std::ops::RangeInclusive<u8> = 3..=12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorMode {
+    Password,
+    Passphrase,
+}
+
+impl GeneratorMode {
+    pub const ALL: [GeneratorMode; 2] = [GeneratorMode::Password, GeneratorMode::Passphrase];
+}
+
+impl fmt::Display for GeneratorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorMode::Password => write!(f, "Password"),
+            GeneratorMode::Passphrase => write!(f, "Passphrase"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    pub mode: GeneratorMode,
+    pub length: u8,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    pub words: u8,
+    pub separator: String,
+    pub capitalize: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::Password,
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    /// Generates a value for the current mode, `None` when no character class is selected.
+    pub fn generate(&self) -> Option<String> {
+        match self.mode {
+            GeneratorMode::Password => self.generate_password(),
+            GeneratorMode::Passphrase => Some(self.generate_passphrase()),
+        }
+    }
+
+    /// Estimated entropy in bits of a value produced with these options.
+    pub fn entropy_bits(&self) -> f64 {
+        match self.mode {
+            GeneratorMode::Password => {
+                let pool = self.classes().iter().map(Vec::len).sum::<usize>();
+                if pool == 0 {
+                    0.0
+                } else {
+                    self.length as f64 * (pool as f64).log2()
+                }
+            }
+            GeneratorMode::Passphrase => self.words as f64 * (wordlist().len() as f64).log2(),
+        }
+    }
+
+    fn classes(&self) -> Vec<Vec<char>> {
+        [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, class)| {
+            class
+                .chars()
+                .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS.contains(*c))
+                .collect()
+        })
+        .collect()
+    }
+
+    fn generate_password(&self) -> Option<String> {
+        let classes = self.classes();
+        let pool: Vec<char> = classes.iter().flatten().copied().collect();
+        if pool.is_empty() {
+            return None;
+        }
+
+        // Draw again until every selected class is present, short passwords can miss one.
+        loop {
+            let password: Vec<char> = (0..self.length)
+                .map(|_| pool[random_index(pool.len())])
+                .collect();
+
+            if self.length as usize <= classes.len()
+                || classes
+                    .iter()
+                    .all(|class| password.iter().any(|c| class.contains(c)))
+            {
+                return Some(password.into_iter().collect());
+            }
+        }
+    }
+
+    fn generate_passphrase(&self) -> String {
+        let words = wordlist();
+        (0..self.words)
+            .map(|_| {
+                let word = words[random_index(words.len())];
+                if self.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+/// Rough strength label for an entropy estimate.
+pub fn strength_label(bits: f64) -> &'static str {
+    match bits {
+        b if b < 40.0 => "Weak",
+        b if b < 60.0 => "Fair",
+        b if b < 80.0 => "Strong",
+        _ => "Very strong",
+    }
+}
+
+fn wordlist() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        WORDLIST
+            .lines()
+            .filter_map(|line| line.split_once('\t').map(|(_, word)| word.trim()))
+            .filter(|word| !word.is_empty())
+            .collect()
+    })
+}
+
+/// Uniform index below `bound`, rejecting the values that would bias the modulo.
+fn random_index(bound: usize) -> usize {
+    let bound = bound as u32;
+    let zone = u32::MAX - u32::MAX % bound;
+    loop {
+        let value = OsRng.next_u32();
+        if value < zone {
+            return (value % bound) as usize;
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password_options() -> GeneratorOptions {
+        GeneratorOptions {
+            length: 12,
+            ..GeneratorOptions::default()
+        }
+    }
+
+    #[test]
+    fn passwords_have_the_length_and_every_class() {
+        let options = password_options();
+        for _ in 0..50 {
+            let password = options.generate().unwrap();
+            assert_eq!(password.chars().count(), 12);
+            for class in [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS] {
+                assert!(password.chars().any(|c| class.contains(c)), "{}", password);
+            }
+        }
+
+        let options = GeneratorOptions {
+            uppercase: false,
+            symbols: false,
+            length: 8,
+            ..password_options()
+        };
+        let password = options.generate().unwrap();
+        assert!(
+            password
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        );
+
+        let options = GeneratorOptions {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..password_options()
+        };
+        assert!(options.generate().is_none());
+    }
+
+    #[test]
+    fn ambiguous_characters_can_be_left_out() {
+        let options = GeneratorOptions {
+            length: 64,
+            exclude_ambiguous: true,
+            ..password_options()
+        };
+        for _ in 0..50 {
+            let password = options.generate().unwrap();
+            assert!(
+                !password.chars().any(|c| AMBIGUOUS.contains(c)),
+                "{}",
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn passphrases_have_the_word_count_and_separator() {
+        let options = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            words: 5,
+            separator: "+".to_string(),
+            capitalize: true,
+            ..GeneratorOptions::default()
+        };
+        let passphrase = options.generate().unwrap();
+        let words: Vec<&str> = passphrase.split('+').collect();
+        assert_eq!(words.len(), 5);
+        for word in words {
+            assert!(
+                word.starts_with(|c: char| c.is_uppercase()),
+                "{}",
+                passphrase
+            );
+        }
+    }
+
+    #[test]
+    fn entropy_matches_the_pool_size() {
+        // 8 digits: 8 * log2(10).
+        let digits = GeneratorOptions {
+            length: 8,
+            lowercase: false,
+            uppercase: false,
+            symbols: false,
+            ..GeneratorOptions::default()
+        };
+        assert!((digits.entropy_bits() - 26.575_424_759).abs() < 1e-6);
+
+        // 6 words of the 7776 word list: 6 * log2(7776).
+        assert_eq!(wordlist().len(), 7776);
+        let passphrase = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            words: 6,
+            ..GeneratorOptions::default()
+        };
+        assert!((passphrase.entropy_bits() - 77.548_875_022).abs() < 1e-6);
+    }
+}
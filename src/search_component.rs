@@ -3,10 +3,53 @@ use iced::{
     Border, Element,
     Length::{Fill, FillPortion},
     Theme,
-    widget::{combo_box, container, row, text_input},
+    widget::{column, combo_box, container, pick_list, row, text, text_input},
 };
 use iced_fonts::lucide;
-use std::fmt::Display;
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use crate::tag_component::tag_chip;
+
+/// Whether an entry needs one or all of the selected tags to be shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    Any,
+    All,
+}
+
+impl TagMatch {
+    pub const ALL: [TagMatch; 2] = [TagMatch::Any, TagMatch::All];
+
+    pub fn matches(self, entry_tags: &[String], selected: &[String]) -> bool {
+        match self {
+            TagMatch::Any => selected.iter().any(|tag| entry_tags.contains(tag)),
+            TagMatch::All => selected.iter().all(|tag| entry_tags.contains(tag)),
+        }
+    }
+}
+
+impl fmt::Display for TagMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagMatch::Any => write!(f, "Any tag (OR)"),
+            TagMatch::All => write!(f, "All tags (AND)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TagFilterChange {
+    Toggle(String),
+    Match(TagMatch),
+}
+
+/// The tags that can be filtered by, the row is hidden when no entry has a tag.
+pub struct TagFilter<'a> {
+    pub tags: &'a [String],
+    pub selected: &'a [String],
+    pub tag_match: TagMatch,
+}
 
 pub fn search<'a, Message, ComboBoxState>(
     value: &str,
@@ -14,12 +57,14 @@ pub fn search<'a, Message, ComboBoxState>(
     state: &'a combo_box::State<ComboBoxState>,
     on_selected: impl Fn(ComboBoxState) -> Message + 'a + 'static,
     selected: Option<&ComboBoxState>,
+    tag_filter: TagFilter<'_>,
+    on_tag_filter: impl Fn(TagFilterChange) -> Message + 'a,
 ) -> Element<'a, Message>
 where
     Message: Clone + 'a,
     ComboBoxState: Display + Clone + 'a + 'static,
 {
-    container(row![
+    let search_row = container(row![
         container(lucide::search())
             .align_x(Center)
             .align_y(Center)
@@ -38,6 +83,41 @@ where
             .width(FillPortion(6)),
         combo_box(state, "Select", selected, on_selected).width(FillPortion(2))
     ])
+    .width(Fill);
+
+    if tag_filter.tags.is_empty() {
+        return search_row.into();
+    }
+
+    let on_tag_filter = Rc::new(on_tag_filter);
+    let on_tag_match = on_tag_filter.clone();
+    let chips = tag_filter
+        .tags
+        .iter()
+        .fold(row![].spacing(5), |chips, tag| {
+            chips.push(tag_chip(
+                tag.clone(),
+                tag_filter.selected.contains(tag),
+                Some(on_tag_filter(TagFilterChange::Toggle(tag.clone()))),
+            ))
+        })
+        .wrap()
+        .vertical_spacing(5);
+
+    column![
+        search_row,
+        row![
+            text("Tags").size(14),
+            pick_list(TagMatch::ALL, Some(tag_filter.tag_match), move |value| {
+                on_tag_match(TagFilterChange::Match(value))
+            })
+            .text_size(14),
+            chips,
+        ]
+        .spacing(10)
+        .align_y(Center),
+    ]
+    .spacing(10)
     .width(Fill)
     .into()
 }
@@ -0,0 +1,86 @@
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// Text that is wiped from memory when it is dropped or replaced. Used for decrypted entries and
+/// the inputs that edit them.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Replaces the value, wiping the old one first.
+    pub fn set(&mut self, value: String) {
+        self.0.zeroize();
+        self.0 = value;
+    }
+
+    pub fn clear(&mut self) {
+        self.0.zeroize();
+    }
+
+    /// Appends `value`. The new text is built in a fresh buffer so a reallocation does not
+    /// leave the old one behind unwiped.
+    pub fn push_str(&mut self, value: &str) {
+        let mut joined = String::with_capacity(self.0.len() + value.len());
+        joined.push_str(&self.0);
+        joined.push_str(value);
+        self.set(joined);
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Zeroize for SecretString {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+/// Never prints the value, so secrets do not end up in logs or panic messages.
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretString(..)")
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
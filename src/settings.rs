@@ -0,0 +1,135 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SETTINGS_DIRECTORY: &str = "kriptografija";
+const SETTINGS_FILE: &str = "settings.txt";
+const DATA_FILE: &str = "data.txt";
+const RECENT_FILES_LIMIT: usize = 8;
+
+/// User preferences kept between sessions as `name=value` lines in the config directory.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub auto_lock_minutes: u32,
+    pub clipboard_clear_seconds: u32,
+    pub backup_count: u32,
+    /// Seconds between writes of the recovery journal, 0 turns autosave off.
+    pub autosave_seconds: u32,
+    /// File opened on start when none is given on the command line.
+    pub default_file: Option<PathBuf>,
+    /// Most recently opened or saved files, newest first.
+    pub recent_files: Vec<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_lock_minutes: 5,
+            clipboard_clear_seconds: 20,
+            backup_count: 5,
+            autosave_seconds: 0,
+            default_file: None,
+            recent_files: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings file, missing or unreadable values fall back to the defaults.
+    pub fn load() -> Self {
+        let mut settings = Settings::default();
+
+        let Some(contents) = settings_path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return settings;
+        };
+
+        for line in contents.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            let value = value.trim();
+            if name.trim() == "default_file" {
+                settings.default_file = (!value.is_empty()).then(|| PathBuf::from(value));
+                continue;
+            }
+            if name.trim() == "recent_file" {
+                let path = PathBuf::from(value);
+                // Files that were moved or deleted since are dropped from the list.
+                if path.exists() && !settings.recent_files.contains(&path) {
+                    settings.recent_files.push(path);
+                }
+                continue;
+            }
+
+            let Ok(number) = value.parse::<u32>() else {
+                continue;
+            };
+
+            match name.trim() {
+                "auto_lock_minutes" => settings.auto_lock_minutes = number,
+                "clipboard_clear_seconds" => settings.clipboard_clear_seconds = number,
+                "backup_count" => settings.backup_count = number,
+                "autosave_seconds" => settings.autosave_seconds = number,
+                _ => {}
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = settings_path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No config directory available")
+        })?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = format!(
+            "auto_lock_minutes={}\nclipboard_clear_seconds={}\nbackup_count={}\nautosave_seconds={}\n",
+            self.auto_lock_minutes,
+            self.clipboard_clear_seconds,
+            self.backup_count,
+            self.autosave_seconds
+        );
+        if let Some(default_file) = &self.default_file {
+            contents.push_str(&format!("default_file={}\n", default_file.display()));
+        }
+        for recent_file in &self.recent_files {
+            contents.push_str(&format!("recent_file={}\n", recent_file.display()));
+        }
+
+        fs::write(path, contents)
+    }
+
+    /// Moves `path` to the front of the recent files, keeping the list short.
+    pub fn add_recent_file(&mut self, path: &Path) {
+        let path = std::path::absolute(path).unwrap_or(path.to_path_buf());
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+    }
+
+    /// The file to open on start, the setting or `data.txt` in the user data directory. Older
+    /// versions kept `data.txt` in the working directory, when it is there it is still used so
+    /// its entries are not hidden behind an empty file.
+    pub fn default_file(&self) -> PathBuf {
+        if let Some(default_file) = &self.default_file {
+            return default_file.clone();
+        }
+
+        let legacy_file = PathBuf::from(DATA_FILE);
+        if legacy_file.is_file() {
+            return legacy_file;
+        }
+        dirs::data_dir()
+            .map(|dir| dir.join(SETTINGS_DIRECTORY).join(DATA_FILE))
+            .unwrap_or(legacy_file)
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(SETTINGS_DIRECTORY).join(SETTINGS_FILE))
+}
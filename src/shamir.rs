@@ -0,0 +1,324 @@
+use std::io;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::vault::parse_attributes;
+
+const CHECKSUM_LENGTH: usize = 4;
+
+/// One point of the sharing polynomials, `index` is the x coordinate and never zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Share {
+    pub index: u8,
+    pub threshold: u8,
+    pub count: u8,
+    /// The shared bytes are the secret followed by the first bytes of its SHA-256, so a bad
+    /// recovery is detected without a share telling anything about the secret.
+    pub data: Vec<u8>,
+}
+
+impl Share {
+    /// Printable form of the share, a short explanation followed by a `<share ...>` line.
+    pub fn to_text(&self, label: &str) -> String {
+        format!(
+            "{} share {} of {}, any {} of them rebuild the entry.\n<share index=\"{}\" threshold=\"{}\" count=\"{}\" data=\"{}\">\n",
+            label,
+            self.index,
+            self.count,
+            self.threshold,
+            self.index,
+            self.threshold,
+            self.count,
+            STANDARD.encode(&self.data),
+        )
+    }
+
+    /// Finds the `<share ...>` line in `text` and parses it.
+    pub fn from_text(text: &str) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with("<share "))
+            .ok_or_else(|| invalid("No share found in the text"))?;
+        let attributes = parse_attributes(line);
+
+        let number = |name: &str| -> io::Result<u8> {
+            attributes
+                .get(name)
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| invalid(&format!("Share is missing a valid {}", name)))
+        };
+        let bytes = |name: &str| -> io::Result<Vec<u8>> {
+            attributes
+                .get(name)
+                .and_then(|value| STANDARD.decode(value).ok())
+                .ok_or_else(|| invalid(&format!("Share is missing a valid {}", name)))
+        };
+
+        let share = Share {
+            index: number("index")?,
+            threshold: number("threshold")?,
+            count: number("count")?,
+            data: bytes("data")?,
+        };
+
+        if share.index == 0
+            || share.index > share.count
+            || share.threshold == 0
+            || share.threshold > share.count
+            || share.data.len() <= CHECKSUM_LENGTH
+        {
+            return Err(invalid("Share has inconsistent parameters"));
+        }
+        Ok(share)
+    }
+}
+
+/// Splits `secret` into `count` shares, any `threshold` of them are enough to rebuild it.
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> io::Result<Vec<Share>> {
+    if threshold == 0 || threshold > count {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Threshold must be between 1 and the number of shares",
+        ));
+    }
+    if secret.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Nothing to split",
+        ));
+    }
+
+    let checksum = checksum(secret);
+    let length = secret.len() + CHECKSUM_LENGTH;
+    let mut shares: Vec<Share> = (1..=count)
+        .map(|index| Share {
+            index,
+            threshold,
+            count,
+            data: Vec::with_capacity(length),
+        })
+        .collect();
+
+    // Every byte gets its own polynomial with the byte as the constant term.
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret.iter().chain(&checksum) {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+
+        for share in shares.iter_mut() {
+            share.data.push(evaluate(&coefficients, share.index));
+        }
+    }
+    coefficients.fill(0);
+
+    Ok(shares)
+}
+
+/// Rebuilds the secret from at least `threshold` distinct shares of the same split.
+pub fn combine(shares: &[Share]) -> io::Result<Vec<u8>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let first = shares.first().ok_or_else(|| invalid("No shares given"))?;
+    if shares.iter().any(|share| {
+        share.threshold != first.threshold
+            || share.count != first.count
+            || share.data.len() != first.data.len()
+    }) {
+        return Err(invalid("Shares belong to different secrets"));
+    }
+
+    let mut indices: Vec<u8> = Vec::new();
+    let mut selected: Vec<&Share> = Vec::new();
+    for share in shares {
+        if !indices.contains(&share.index) {
+            indices.push(share.index);
+            selected.push(share);
+        }
+    }
+    if selected.len() < first.threshold as usize {
+        return Err(invalid(&format!(
+            "{} different shares are needed, {} given",
+            first.threshold,
+            selected.len()
+        )));
+    }
+    selected.truncate(first.threshold as usize);
+    indices.truncate(first.threshold as usize);
+
+    let mut secret: Vec<u8> = (0..first.data.len())
+        .map(|position| {
+            let points: Vec<(u8, u8)> = selected
+                .iter()
+                .map(|share| (share.index, share.data[position]))
+                .collect();
+            interpolate_at_zero(&points)
+        })
+        .collect();
+
+    let expected = secret.split_off(secret.len().saturating_sub(CHECKSUM_LENGTH));
+    if secret.is_empty() || checksum(&secret) != expected.as_slice() {
+        return Err(invalid("Shares do not rebuild the original entry"));
+    }
+    Ok(secret)
+}
+
+fn checksum(secret: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    let digest = Sha256::digest(secret);
+    let mut checksum = [0u8; CHECKSUM_LENGTH];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LENGTH]);
+    checksum
+}
+
+/// Horner evaluation of the polynomial at `x` in GF(256).
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |result, &coefficient| gf_mul(result, x) ^ coefficient)
+}
+
+/// Lagrange interpolation of the polynomial value at zero.
+fn interpolate_at_zero(points: &[(u8, u8)]) -> u8 {
+    points.iter().fold(0, |result, &(xi, yi)| {
+        let basis = points
+            .iter()
+            .filter(|&&(xj, _)| xj != xi)
+            .fold(1, |basis, &(xj, _)| {
+                // (0 - xj) / (xi - xj), subtraction is xor in GF(256).
+                gf_mul(basis, gf_div(xj, xi ^ xj))
+            });
+        result ^ gf_mul(yi, basis)
+    })
+}
+
+/// Multiplication modulo the AES polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverse through a^254, every non zero element satisfies a^255 = 1.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    gf_mul(a, gf_inv(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_inverse_for_every_element() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {}", a);
+        }
+    }
+
+    #[test]
+    fn field_multiplication_matches_known_values() {
+        // Values from the AES specification.
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        assert_eq!(gf_mul(0, 0x13), 0);
+    }
+
+    #[test]
+    fn any_threshold_subset_rebuilds_the_secret() {
+        let secret = b"abandon ability able about above absent".to_vec();
+        let shares = split(&secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(combine(&subset).unwrap(), secret);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn too_few_shares_are_rejected() {
+        let shares = split(b"secret", 3, 5).unwrap();
+        assert!(combine(&shares[..2]).is_err());
+        // A repeated share does not count twice.
+        assert!(combine(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn tampered_share_is_detected() {
+        let mut shares = split(b"secret", 2, 3).unwrap();
+        shares[1].data[0] ^= 1;
+        assert!(combine(&shares[..2]).is_err());
+    }
+
+    #[test]
+    fn single_share_threshold_is_the_secret() {
+        let shares = split(b"secret", 1, 2).unwrap();
+        assert!(shares[0].data.starts_with(b"secret"));
+        assert_eq!(combine(&shares[1..]).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert!(split(b"secret", 0, 3).is_err());
+        assert!(split(b"secret", 4, 3).is_err());
+        assert!(split(b"", 2, 3).is_err());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let shares = split(b"secret", 2, 3).unwrap();
+        let text = shares[2].to_text("Recovery Seed");
+        assert_eq!(Share::from_text(&text).unwrap(), shares[2]);
+        // Nothing but the data depends on the secret.
+        assert!(!text.contains("checksum"));
+        assert!(Share::from_text("no share here").is_err());
+    }
+
+    #[test]
+    fn share_index_must_be_one_of_the_shares() {
+        let share = split(b"secret", 2, 3).unwrap().remove(0);
+        for index in [0, 4] {
+            let text = Share {
+                index,
+                ..share.clone()
+            }
+            .to_text("Mail");
+            let error = Share::from_text(&text).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
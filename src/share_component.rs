@@ -0,0 +1,135 @@
+use std::rc::Rc;
+
+use iced::Alignment::Center;
+use iced::Length::{self, Fill};
+use iced::widget::{Column, button, column, container, row, scrollable, text, text_input};
+use iced::{Border, Element, Font, Theme};
+
+use crate::shamir::Share;
+
+pub enum ShareChange {
+    Count(String),
+    Threshold(String),
+    Split,
+    Copy(String),
+    Text(String),
+    AddText,
+    BrowseFiles,
+}
+
+pub fn share_export<'a, Message>(
+    label_width: Length,
+    key: &str,
+    count: &str,
+    threshold: &str,
+    is_invalid: bool,
+    shares: &[String],
+    on_input: impl Fn(ShareChange) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let on_input = Rc::new(on_input);
+    let on_input_count = on_input.clone();
+    let on_input_threshold = on_input.clone();
+
+    let mut content: Column<'a, Message> = column![
+        text(format!(
+            "Split \"{}\" into shares, any threshold of them rebuild the entry. Okay saves every share to its own file.",
+            key
+        ))
+        .size(16),
+        number_row(label_width, "Shares", count, is_invalid, move |value| {
+            on_input_count(ShareChange::Count(value))
+        }),
+        number_row(label_width, "Threshold", threshold, is_invalid, move |value| {
+            on_input_threshold(ShareChange::Threshold(value))
+        }),
+        button(text("Show printable shares")).on_press(on_input(ShareChange::Split)),
+    ]
+    .spacing(15);
+
+    if !shares.is_empty() {
+        let list = shares.iter().fold(column![].spacing(10), |list, share| {
+            list.push(
+                row![
+                    container(text(share.clone()).size(12).font(Font::MONOSPACE)).width(Fill),
+                    button(text("Copy")).on_press(on_input(ShareChange::Copy(share.clone()))),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            )
+        });
+        content = content.push(scrollable(list).height(250));
+    }
+
+    content.into()
+}
+
+pub fn share_recovery<'a, Message>(
+    shares: &[Share],
+    share_text: &str,
+    on_input: impl Fn(ShareChange) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let on_input = Rc::new(on_input);
+    let on_input_text = on_input.clone();
+
+    let needed = shares.first().map(|share| share.threshold);
+    let status = match needed {
+        Some(threshold) => format!("{} of {} shares added.", shares.len(), threshold),
+        None => "Add shares from files or paste their text below.".to_string(),
+    };
+
+    let list = shares.iter().fold(column![].spacing(5), |list, share| {
+        list.push(text(format!("Share {} of {}", share.index, share.count)).size(14))
+    });
+
+    column![
+        text(status).size(16),
+        list,
+        row![
+            text_input("<share index=...>", share_text)
+                .on_input(move |value| on_input_text(ShareChange::Text(value)))
+                .on_submit(on_input(ShareChange::AddText)),
+            button(text("Add")).on_press(on_input(ShareChange::AddText)),
+            button(text("Browse")).on_press(on_input(ShareChange::BrowseFiles)),
+        ]
+        .spacing(10)
+        .align_y(Center),
+    ]
+    .spacing(15)
+    .into()
+}
+
+fn number_row<'a, Message>(
+    label_width: Length,
+    label: &'a str,
+    value: &str,
+    is_invalid: bool,
+    on_input: impl Fn(String) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    row![
+        container(text(label).size(16).width(label_width).align_y(Center)).padding(5),
+        text_input(label, value)
+            .style(move |theme: &Theme, status| {
+                let mut style = text_input::default(theme, status);
+                if is_invalid {
+                    style.border = Border {
+                        color: theme.palette().danger,
+                        width: 1.0,
+                        ..Default::default()
+                    };
+                }
+                style
+            })
+            .on_input(on_input)
+    ]
+    .spacing(10)
+    .into()
+}
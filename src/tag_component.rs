@@ -0,0 +1,40 @@
+use iced::widget::{button, text};
+use iced::{Border, Element, Theme};
+
+/// A small rounded tag. `selected` tags use the primary color, `on_press` makes the chip
+/// clickable.
+pub fn tag_chip<'a, Message>(
+    label: String,
+    selected: bool,
+    on_press: Option<Message>,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    button(text(label).size(12))
+        .padding([2, 8])
+        .on_press_maybe(on_press)
+        .style(move |theme: &Theme, status| {
+            let palette = theme.extended_palette();
+            let pair = if selected {
+                palette.primary.base
+            } else {
+                palette.background.strong
+            };
+            let background = match status {
+                button::Status::Hovered => pair.color.scale_alpha(0.8),
+                _ => pair.color,
+            };
+
+            button::Style {
+                background: Some(background.into()),
+                text_color: pair.text,
+                border: Border {
+                    radius: 10.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use zeroize::Zeroizing;
+
+use crate::secret_string::SecretString;
+
+pub const DEFAULT_DIGITS: u32 = 6;
+pub const DEFAULT_PERIOD: u64 = 30;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    /// Accepts the names used by `otpauth://` links, `SHA1`, `SHA256` and `SHA512`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().replace('-', "").as_str() {
+            "SHA1" => Some(TotpAlgorithm::Sha1),
+            "SHA256" => Some(TotpAlgorithm::Sha256),
+            "SHA512" => Some(TotpAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TotpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TotpAlgorithm::Sha1 => write!(f, "SHA1"),
+            TotpAlgorithm::Sha256 => write!(f, "SHA256"),
+            TotpAlgorithm::Sha512 => write!(f, "SHA512"),
+        }
+    }
+}
+
+/// Time based one time password settings as defined by RFC 6238, the secret is base32 text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Totp {
+    pub secret: SecretString,
+    pub digits: u32,
+    pub period: u64,
+    pub algorithm: TotpAlgorithm,
+}
+
+impl Totp {
+    /// Code valid at `unix_time`, `None` when the secret is not valid base32.
+    pub fn code_at(&self, unix_time: u64) -> Option<String> {
+        let key = decode_base32(&self.secret)?;
+        Some(hotp(
+            &key,
+            unix_time / self.period.max(1),
+            self.digits,
+            self.algorithm,
+        ))
+    }
+
+    /// Seconds until the code at `unix_time` changes.
+    pub fn seconds_left(&self, unix_time: u64) -> u64 {
+        let period = self.period.max(1);
+        period - unix_time % period
+    }
+}
+
+/// HOTP from RFC 4226 with the hash selectable as RFC 6238 allows.
+fn hotp(key: &[u8], counter: u64, digits: u32, algorithm: TotpAlgorithm) -> String {
+    let message = counter.to_be_bytes();
+    let digest = match algorithm {
+        TotpAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, &message),
+        TotpAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, &message),
+        TotpAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, &message),
+    };
+
+    // Dynamic truncation, the low nibble of the last byte picks four bytes of the digest.
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    let digits = digits.clamp(1, 9);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(digits),
+        width = digits as usize
+    )
+}
+
+fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    Zeroizing::new(mac.finalize().into_bytes().to_vec())
+}
+
+/// Decodes RFC 4648 base32, ignoring case, spaces and padding as authenticator apps do.
+fn decode_base32(value: &str) -> Option<Zeroizing<Vec<u8>>> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity(value.len() * 5 / 8));
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in value.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let index = BASE32_ALPHABET
+            .iter()
+            .position(|&letter| letter == c.to_ascii_uppercase() as u8)?;
+        buffer = (buffer << 5) | index as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base32(bytes: &[u8]) -> String {
+        let mut encoded = String::new();
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        for &byte in bytes {
+            buffer = (buffer << 8) | byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+        }
+        encoded
+    }
+
+    fn rfc_totp(algorithm: TotpAlgorithm) -> Totp {
+        // Appendix B of RFC 6238 repeats the ASCII seed to the length of each hash output.
+        let seed: &[u8] = match algorithm {
+            TotpAlgorithm::Sha1 => b"12345678901234567890",
+            TotpAlgorithm::Sha256 => b"12345678901234567890123456789012",
+            TotpAlgorithm::Sha512 => {
+                b"1234567890123456789012345678901234567890123456789012345678901234"
+            }
+        };
+        Totp {
+            secret: base32(seed).into(),
+            digits: 8,
+            period: 30,
+            algorithm,
+        }
+    }
+
+    #[test]
+    fn rfc_6238_test_vectors() {
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+
+        for (time, sha1, sha256, sha512) in vectors {
+            let at = |algorithm| rfc_totp(algorithm).code_at(time).unwrap();
+            assert_eq!(at(TotpAlgorithm::Sha1), sha1, "SHA1 at {}", time);
+            assert_eq!(at(TotpAlgorithm::Sha256), sha256, "SHA256 at {}", time);
+            assert_eq!(at(TotpAlgorithm::Sha512), sha512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn six_digit_codes_are_the_low_digits() {
+        let mut totp = rfc_totp(TotpAlgorithm::Sha1);
+        totp.digits = 6;
+        assert_eq!(totp.code_at(59).unwrap(), "287082");
+        assert_eq!(totp.code_at(1111111109).unwrap(), "081804");
+    }
+
+    #[test]
+    fn base32_decoding() {
+        assert_eq!(decode_base32("MZXW6YTBOI").unwrap().as_slice(), b"foobar");
+        assert_eq!(
+            decode_base32("mzxw 6ytb oi======").unwrap().as_slice(),
+            b"foobar"
+        );
+        assert_eq!(decode_base32("MY======").unwrap().as_slice(), b"f");
+        assert!(decode_base32("not base32!").is_none());
+        assert!(decode_base32("").is_none());
+    }
+
+    #[test]
+    fn countdown_reaches_the_next_period() {
+        let totp = rfc_totp(TotpAlgorithm::Sha1);
+        assert_eq!(totp.seconds_left(59), 1);
+        assert_eq!(totp.seconds_left(60), 30);
+    }
+}
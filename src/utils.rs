@@ -1,6 +1,10 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use iced::Color;
 use iced::window::Icon;
@@ -9,31 +13,399 @@ use image::error::DecodingError;
 use image::{GenericImageView, ImageError, ImageResult};
 use palette::{FromColor, Hsl, Srgb};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::base_description_component::{
-    DescriptionElement, parse_description_elements, serialize_description_elements,
+    DescriptionElement, DescriptionImage, DescriptionSecret, DescriptionSound, DescriptionTotp,
+    Link, escape_attribute, parse_description_elements, plain_description,
+    serialize_description_elements, unescape_attribute,
 };
+use crate::csv_import::single_line;
+use crate::custom_field::{CustomField, FieldKind};
+use crate::secret_string::SecretString;
+use crate::totp::{Totp, TotpAlgorithm};
+use crate::vault::{self, Credentials, KdfParams, LockedVault, UnlockFactors, VaultKey};
 
-pub const FILE_VERSION: u32 = 2;
+pub const FILE_VERSION: u32 = 9;
+/// Earlier versions kept for every entry, the oldest are dropped first.
+pub const HISTORY_LIMIT: usize = 10;
+pub const DEFAULT_BACKUP_COUNT: usize = 5;
+const BACKUP_EXTENSION: &str = "bak";
+const JOURNAL_EXTENSION: &str = "journal";
+const JSON_VERSION: u32 = 1;
+
+/// How the entry lines of one file version are read.
+struct FileFormat {
+    version: u32,
+    /// Everything after the version and header lines is encrypted.
+    encrypted: bool,
+    /// Shown when a line cannot be parsed.
+    line_syntax: &'static str,
+    parse_line: fn(&str) -> Option<DataEntry>,
+    /// Reads the lines with earlier versions that follow their entry, `None` before version 6.
+    parse_revision: Option<fn(&str) -> Option<Revision>>,
+    /// Reads the lines with custom fields that follow their entry, `None` before version 9.
+    parse_field: Option<fn(&str) -> Option<CustomField>>,
+}
+
+/// Every version that can be opened, see the matching `filetypev{version}.desc`.
+const FILE_FORMATS: [FileFormat; 9] = [
+    FileFormat {
+        version: 1,
+        encrypted: false,
+        line_syntax: "key:desc",
+        parse_line: parse_plain_line,
+        parse_revision: None,
+        parse_field: None,
+    },
+    FileFormat {
+        version: 2,
+        encrypted: false,
+        line_syntax: "key:desc",
+        parse_line: parse_tagged_line,
+        parse_revision: None,
+        parse_field: None,
+    },
+    FileFormat {
+        version: 3,
+        encrypted: true,
+        line_syntax: "key:desc",
+        parse_line: parse_tagged_line,
+        parse_revision: None,
+        parse_field: None,
+    },
+    FileFormat {
+        version: 4,
+        encrypted: true,
+        line_syntax: "id:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: None,
+        parse_field: None,
+    },
+    FileFormat {
+        version: 5,
+        encrypted: true,
+        line_syntax: "id attributes:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: None,
+        parse_field: None,
+    },
+    FileFormat {
+        version: 6,
+        encrypted: true,
+        line_syntax: "id attributes:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: Some(parse_revision_line),
+        parse_field: None,
+    },
+    FileFormat {
+        version: 7,
+        encrypted: true,
+        line_syntax: "id attributes:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: Some(parse_revision_line),
+        parse_field: None,
+    },
+    FileFormat {
+        version: 8,
+        encrypted: true,
+        line_syntax: "id attributes:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: Some(parse_revision_line),
+        parse_field: None,
+    },
+    FileFormat {
+        version: 9,
+        encrypted: true,
+        line_syntax: "id attributes:key:desc",
+        parse_line: parse_identified_line,
+        parse_revision: Some(parse_revision_line),
+        parse_field: Some(parse_field_line),
+    },
+];
+
+fn file_format(version: u32) -> io::Result<&'static FileFormat> {
+    FILE_FORMATS
+        .iter()
+        .find(|format| format.version == version)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported file version {}", version),
+            )
+        })
+}
+
+/// What was changed while bringing an older file up to `FILE_VERSION`.
+#[derive(Clone, Debug)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<String>,
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The file was upgraded from version {} to version {}.",
+            self.from_version, self.to_version
+        )?;
+        for change in &self.changes {
+            write!(f, "\n• {}", change)?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct DataEntry {
     pub id: Uuid,
-    pub key: String,
+    pub key: SecretString,
+    pub description: Vec<DescriptionElement>,
+    /// Unix seconds, 0 when unknown because the entry comes from a file older than version 5.
+    pub created: u64,
+    pub modified: u64,
+    /// Earlier versions, oldest first and at most `HISTORY_LIMIT` of them.
+    pub history: Vec<Revision>,
+    /// In the order they were added, see `split_tags` for what a tag may contain.
+    pub tags: Vec<String>,
+    /// `/` separated path like `House/Garage`, empty for entries that are not in a folder.
+    /// See `normalize_folder`.
+    pub folder: String,
+    pub fields: Vec<CustomField>,
+}
+
+/// A key and description an entry had before it was edited.
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub key: SecretString,
     pub description: Vec<DescriptionElement>,
-    pub description_raw: String,
+    /// When the entry was changed to this version, 0 when unknown.
+    pub modified: u64,
+}
+
+impl Revision {
+    /// The `revision modified="{unix seconds}":key:desc` line written after its entry.
+    fn to_file_line(&self) -> SecretString {
+        file_line(
+            &format!("revision modified=\"{}\"", self.modified),
+            &self.key,
+            &self.description,
+        )
+    }
 }
 
 impl DataEntry {
     pub fn new(key: &str, description: &str) -> Self {
+        Self::with_description(key, parse_description_elements(description))
+    }
+
+    /// A new entry created now.
+    pub fn with_description(key: &str, description: Vec<DescriptionElement>) -> Self {
+        let now = unix_time();
         Self {
             id: Uuid::new_v4(),
-            key: key.to_string(),
-            description: parse_description_elements(description.to_string()),
-            description_raw: description.to_string(),
+            key: key.into(),
+            description,
+            created: now,
+            modified: now,
+            history: Vec::new(),
+            tags: Vec::new(),
+            folder: String::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Replaces the key and description, the previous ones are kept in the history. Nothing
+    /// changes when both are the same.
+    pub fn update(&mut self, key: SecretString, description: Vec<DescriptionElement>) {
+        if self.key == key
+            && serialize_description_elements(&self.description)
+                == serialize_description_elements(&description)
+        {
+            return;
+        }
+        self.history.push(Revision {
+            key: std::mem::replace(&mut self.key, key),
+            description: std::mem::replace(&mut self.description, description),
+            modified: self.modified,
+        });
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.drain(..self.history.len() - HISTORY_LIMIT);
+        }
+        self.modified = unix_time();
+    }
+
+    /// Replaces the tags, the modified time only changes when they do.
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        if self.tags != tags {
+            self.tags = tags;
+            self.modified = unix_time();
+        }
+    }
+
+    /// Replaces the custom fields, the modified time only changes when they do.
+    pub fn set_fields(&mut self, fields: Vec<CustomField>) {
+        if self.fields != fields {
+            self.fields = fields;
+            self.modified = unix_time();
+        }
+    }
+
+    /// Moves the entry to `folder`, the modified time only changes when it is a different one.
+    pub fn set_folder(&mut self, folder: &str) {
+        let folder = normalize_folder(folder);
+        if self.folder != folder {
+            self.folder = folder;
+            self.modified = unix_time();
+        }
+    }
+
+    /// Whether the entry is in `folder` or one of its subfolders.
+    pub fn is_in_folder(&self, folder: &str) -> bool {
+        folder.is_empty()
+            || self
+                .folder
+                .strip_prefix(folder)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// Makes the revision at `index` the current version, the current one goes to the history
+    /// so the revert can be reverted as well.
+    pub fn revert(&mut self, index: usize) -> bool {
+        match self.history.get(index).cloned() {
+            Some(revision) => {
+                self.update(revision.key, revision.description);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `key:desc` line this entry is stored as, without the line break.
+    pub fn to_line(&self) -> SecretString {
+        entry_line(&self.key, &self.description)
+    }
+
+    /// Parses a single `key:desc` line, the entry gets a new id and is created now.
+    pub fn from_line(line: &str) -> io::Result<Self> {
+        parse_tagged_line(line)
+            .map(|entry| Self::with_description(&entry.key, entry.description.clone()))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed entry"))
+    }
+
+    /// The `key:desc` line followed by a line for every custom field, what the shares of an
+    /// entry hold.
+    pub fn to_share_text(&self) -> SecretString {
+        let mut text = self.to_line();
+        for field in &self.fields {
+            text.push_str("\n");
+            text.push_str(&field_line(field));
+        }
+        text
+    }
+
+    /// Parses what `to_share_text` wrote, shares made before custom fields only have the line.
+    pub fn from_share_text(text: &str) -> io::Result<Self> {
+        let mut lines = text.lines();
+        let mut entry = Self::from_line(lines.next().unwrap_or_default())?;
+        entry.fields = lines
+            .map(|line| {
+                parse_field_line(line).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Malformed custom field")
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(entry)
+    }
+
+    /// The `id attributes:key:desc` line this entry is stored as in the file.
+    fn to_file_line(&self) -> SecretString {
+        let mut header = format!(
+            "{} created=\"{}\" modified=\"{}\"",
+            self.id.hyphenated(),
+            self.created,
+            self.modified
+        );
+        if !self.tags.is_empty() {
+            header.push_str(&format!(
+                " tags=\"{}\"",
+                escape_attribute(&self.tags.join(","))
+            ));
+        }
+        if !self.folder.is_empty() {
+            header.push_str(&format!(" folder=\"{}\"", escape_attribute(&self.folder)));
+        }
+        file_line(&header, &self.key, &self.description)
+    }
+}
+
+/// Trims every part of a `/` separated folder path and drops the empty ones, so `" House//Garage/"`
+/// becomes `House/Garage`.
+pub fn normalize_folder(folder: &str) -> String {
+    folder
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Tags are separated by commas, so a tag is whatever is between them with the spaces around it
+/// removed.
+pub fn split_tags(value: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in value
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
         }
     }
+    tags
+}
+
+fn entry_line(key: &str, description: &[DescriptionElement]) -> SecretString {
+    let description = serialize_description_elements(description);
+    let mut line = String::with_capacity(escaped_len(key) + 1 + escaped_len(&description));
+    push_escaped(&mut line, key);
+    line.push(':');
+    push_escaped(&mut line, &description);
+    SecretString::from(line)
+}
+
+/// `field type="{kind}" name="{name}":value`, written after its entry.
+fn field_line(field: &CustomField) -> SecretString {
+    let header = format!(
+        "field type=\"{}\" name=\"{}\"",
+        field.kind.name(),
+        escape_attribute(&field.name)
+    );
+    let mut line = String::with_capacity(escaped_len(&header) + 1 + escaped_len(&field.value));
+    push_escaped(&mut line, &header);
+    line.push(':');
+    push_escaped(&mut line, &field.value);
+    SecretString::from(line)
+}
+
+/// `header:key:desc`, the header is escaped like the key.
+fn file_line(header: &str, key: &str, description: &[DescriptionElement]) -> SecretString {
+    let line = entry_line(key, description);
+    let mut file_line = String::with_capacity(escaped_len(header) + 1 + line.len());
+    push_escaped(&mut file_line, header);
+    file_line.push(':');
+    file_line.push_str(&line);
+    SecretString::from(file_line)
+}
+
+pub enum LoadStatus {
+    /// Carries a report when the file was written by an older version.
+    Loaded(Option<MigrationReport>),
+    Locked(LockedVault),
 }
 
 pub struct AppData<Message>
@@ -42,6 +414,9 @@ where
 {
     pub version: u32,
     pub entries: Vec<DataEntry>,
+    /// How many previous versions `save_file` keeps next to the file.
+    pub backup_count: usize,
+    vault_key: Option<VaultKey>,
     _message: Message,
 }
 
@@ -53,11 +428,68 @@ where
         AppData {
             version: FILE_VERSION,
             entries: Vec::new(),
+            backup_count: DEFAULT_BACKUP_COUNT,
+            vault_key: None,
             _message: none,
         }
     }
 
-    pub fn load_file(&mut self, filename: String) -> io::Result<()> {
+    pub fn has_password(&self) -> bool {
+        self.vault_key.is_some()
+    }
+
+    pub fn set_password(&mut self, credentials: &Credentials) -> io::Result<()> {
+        self.vault_key = Some(VaultKey::new(credentials, KdfParams::default())?);
+        Ok(())
+    }
+
+    pub fn kdf_params(&self) -> Option<KdfParams> {
+        self.vault_key.as_ref().map(|key| key.kdf)
+    }
+
+    pub fn unlock_factors(&self) -> Option<UnlockFactors> {
+        self.vault_key.as_ref().map(|key| key.factors)
+    }
+
+    /// Re-encrypts the file under new credentials and a fresh salt. The current key is only
+    /// replaced once the new file has been written completely.
+    pub fn change_password(
+        &mut self,
+        filename: String,
+        current_password: &str,
+        new_credentials: &Credentials,
+        kdf: KdfParams,
+    ) -> io::Result<()> {
+        let current_key = self.vault_key.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "No password set for this file",
+            )
+        })?;
+
+        if !current_key.matches(current_password)? {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Current password is wrong",
+            ));
+        }
+
+        let new_key = VaultKey::new(new_credentials, kdf)?;
+        let contents = self.encode_file(&new_key)?;
+        let path = Path::new(&filename);
+        backup_file(path, self.backup_count)?;
+        write_atomic(path, contents.as_bytes())?;
+        // The journal was encrypted with the old key.
+        remove_journal(path)?;
+
+        self.vault_key = Some(new_key);
+        Ok(())
+    }
+
+    /// Reads a file. Plaintext files are loaded right away, encrypted ones are returned locked
+    /// and have to be passed to `unlock` together with the password. The entries and key of the
+    /// previous file are forgotten either way, so they can not be saved over the new one.
+    pub fn load_file(&mut self, filename: String) -> io::Result<LoadStatus> {
         let path = Path::new(&filename);
 
         if !path.exists() {
@@ -71,11 +503,9 @@ where
         let reader = BufReader::new(file);
         let mut lines = reader.lines();
 
-        let reg = Regex::new(r"(?P<key>(?:\\:|[^:])+):(?P<desc>.*)").unwrap();
-
         let first_line = lines
             .next()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "File is empty"))??;
+            .ok_or_else(|| io::Error::other("File is empty"))??;
 
         let version = first_line
             .trim()
@@ -88,57 +518,946 @@ where
                 "Version mismatch",
             ));
         }
+        let format = file_format(version)?;
+
+        if format.encrypted {
+            let header_line = lines.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Missing vault header")
+            })??;
+            let body = lines.collect::<io::Result<Vec<String>>>()?.join("");
+            let vault = LockedVault::parse(version, &header_line, &body)?;
+
+            self.entries.clear();
+            self.vault_key = None;
+            return Ok(LoadStatus::Locked(vault));
+        }
+
+        self.entries = parse_entries(lines, 2, format)?;
+        self.vault_key = None;
+        self.version = FILE_VERSION;
+        Ok(LoadStatus::Loaded(migrate(&self.entries, version)))
+    }
+
+    /// Decrypts a locked file, returns a report when it was written by an older version.
+    pub fn unlock(
+        &mut self,
+        vault: &LockedVault,
+        credentials: &Credentials,
+    ) -> io::Result<Option<MigrationReport>> {
+        let format = file_format(vault.version)?;
+        let (key, body) = vault.unlock(credentials)?;
+
+        self.entries = parse_entries(body.lines().map(Ok), 1, format)?;
+        self.vault_key = Some(key);
+        self.version = FILE_VERSION;
+        Ok(migrate(&self.entries, vault.version))
+    }
+
+    /// Encrypts the entries in memory and forgets both them and the key, the returned vault is
+    /// unlocked again with `unlock`.
+    pub fn lock(&mut self) -> io::Result<LockedVault> {
+        let key = self.vault_key.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "No password set for this file",
+            )
+        })?;
+        let sealed = vault::seal(key, self.version, &self.serialize_entries())?;
+
+        self.entries.clear();
+        self.vault_key = None;
+        Ok(sealed)
+    }
+
+    pub fn save_file(&self, filename: String) -> Result<()> {
+        let key = self.vault_key.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "No password set for this file",
+            )
+        })?;
+        let contents = self.encode_file(key)?;
+        let path = Path::new(&filename);
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        backup_file(path, self.backup_count)?;
+        write_atomic(path, contents.as_bytes())?;
+        remove_journal(path)
+    }
+
+    /// Writes the entries to the recovery journal of `filename`, encrypted with the same key
+    /// as the file so it is unlocked the same way.
+    pub fn write_journal(&self, filename: &Path) -> Result<()> {
+        let key = self.vault_key.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "No password set for this file",
+            )
+        })?;
+        let contents = self.encode_file(key)?;
+        write_atomic(&journal_path(filename), contents.as_bytes())
+    }
+
+    fn encode_file(&self, key: &VaultKey) -> io::Result<String> {
+        let sealed = vault::seal(key, self.version, &self.serialize_entries())?;
+
+        Ok(format!(
+            "{}\n{}\n{}\n",
+            self.version,
+            sealed.header_line(),
+            sealed.encoded_body()
+        ))
+    }
+
+    /// Adds `entries`, replacing the ones that share an id. Returns the added and replaced counts.
+    pub fn merge_entries(&mut self, entries: Vec<DataEntry>) -> (usize, usize) {
+        let mut added = 0;
+        let mut replaced = 0;
+        for entry in entries {
+            match self
+                .entries
+                .iter_mut()
+                .find(|existing| existing.id == entry.id)
+            {
+                Some(existing) => {
+                    *existing = entry;
+                    replaced += 1;
+                }
+                None => {
+                    self.entries.push(entry);
+                    added += 1;
+                }
+            }
+        }
+        (added, replaced)
+    }
+
+    /// Adds `entries`, an entry with the key of an existing one replaces its description.
+    /// Returns the added and merged counts.
+    pub fn merge_entries_by_key(&mut self, entries: Vec<DataEntry>) -> (usize, usize) {
+        let mut added = 0;
+        let mut merged = 0;
+        for entry in entries {
+            match self
+                .entries
+                .iter_mut()
+                .find(|existing| existing.key == entry.key)
+            {
+                Some(existing) => {
+                    existing.update(entry.key, entry.description);
+                    merged += 1;
+                }
+                None => {
+                    self.entries.push(entry);
+                    added += 1;
+                }
+            }
+        }
+        (added, merged)
+    }
 
-        let mut new_entries = Vec::new();
+    /// Every tag used by an entry, sorted.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.tags.iter().cloned())
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        tags
+    }
 
-        for (index, line_result) in lines.enumerate() {
-            let line = line_result?;
-            if line.trim().is_empty() {
-                continue;
+    /// Every folder with the number of entries in it and its subfolders. Parents come before
+    /// their subfolders, which come before the next folder on the same level.
+    pub fn folders(&self) -> Vec<(String, usize)> {
+        let mut folders: Vec<String> = Vec::new();
+        for entry in self.entries.iter().filter(|entry| !entry.folder.is_empty()) {
+            // `House/Garage` also makes `House` a folder.
+            for (end, _) in entry
+                .folder
+                .match_indices('/')
+                .chain(std::iter::once((entry.folder.len(), "")))
+            {
+                let folder = &entry.folder[..end];
+                if !folders.iter().any(|existing| existing == folder) {
+                    folders.push(folder.to_string());
+                }
             }
+        }
+        folders.sort_by_cached_key(|folder| {
+            folder.split('/').map(str::to_lowercase).collect::<Vec<_>>()
+        });
+
+        folders
+            .into_iter()
+            .map(|folder| {
+                let count = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.is_in_folder(&folder))
+                    .count();
+                (folder, count)
+            })
+            .collect()
+    }
+
+    fn serialize_entries(&self) -> SecretString {
+        let lines: Vec<SecretString> = self
+            .entries
+            .iter()
+            .flat_map(|entry| {
+                std::iter::once(entry.to_file_line())
+                    .chain(entry.fields.iter().map(field_line))
+                    .chain(entry.history.iter().map(Revision::to_file_line))
+            })
+            .collect();
+
+        let mut serialized = String::with_capacity(lines.iter().map(|line| line.len() + 1).sum());
+        for line in &lines {
+            serialized.push_str(line);
+            serialized.push('\n');
+        }
+        SecretString::from(serialized)
+    }
+}
 
-            if let Some(caps) = reg.captures(&line) {
-                let key = caps["key"].replace(r"\:", ":");
-                let desc = caps["desc"].replace(r"\:", ":");
+/// Parses `key:desc` lines with the grammar of `format`, `first_line_number` is only used for
+/// error messages.
+fn parse_entries<Line: AsRef<str>>(
+    lines: impl Iterator<Item = io::Result<Line>>,
+    first_line_number: usize,
+    format: &FileFormat,
+) -> io::Result<Vec<DataEntry>> {
+    let mut new_entries: Vec<DataEntry> = Vec::new();
+    let mut ids = HashSet::new();
 
-                new_entries.push(DataEntry::new(key.trim(), desc.trim()));
-            } else {
+    for (index, line_result) in lines.enumerate() {
+        let line = line_result?;
+        let line = line.as_ref();
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(revision) = format.parse_revision.and_then(|parse| parse(line)) {
+            last_entry(&mut new_entries, "Revision", index + first_line_number)?
+                .history
+                .push(revision);
+            continue;
+        }
+        if let Some(field) = format.parse_field.and_then(|parse| parse(line)) {
+            last_entry(&mut new_entries, "Field", index + first_line_number)?
+                .fields
+                .push(field);
+            continue;
+        }
+
+        match (format.parse_line)(line) {
+            Some(mut entry) => {
+                // A copied line must not make two entries share an id.
+                if !ids.insert(entry.id) {
+                    entry.id = Uuid::new_v4();
+                    ids.insert(entry.id);
+                }
+                new_entries.push(entry);
+            }
+            None => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
                     format!(
-                        "Malformed line at #{} (expected 'key:desc'): {}",
-                        index + 2,
+                        "Malformed line at #{} (expected '{}'): {}",
+                        index + first_line_number,
+                        format.line_syntax,
                         line
                     ),
                 ));
             }
         }
-        self.entries = new_entries;
-        Ok(())
     }
 
-    pub fn save_file(&self, filename: String) -> Result<()> {
-        match File::create(filename) {
-            Ok(mut file) => {
-                use std::io::Write;
-                writeln!(file, "{}", self.version)?;
-
-                for entry in &self.entries {
-                    writeln!(
-                        file,
-                        "{}:{}",
-                        entry.key.replace(":", r"\:"),
-                        serialize_description_elements(entry.description.clone())
-                            .replace(":", r"\:")
-                    )?
+    Ok(new_entries)
+}
+
+/// The entry that revision and field lines belong to.
+fn last_entry<'a>(
+    entries: &'a mut [DataEntry],
+    line_kind: &str,
+    line_number: usize,
+) -> io::Result<&'a mut DataEntry> {
+    entries.last_mut().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} without an entry at #{}", line_kind, line_number),
+        )
+    })
+}
+
+/// Version 1 lines, the key ends at the first `:` and the description is plain text.
+fn parse_plain_line(line: &str) -> Option<DataEntry> {
+    let (key, description) = line.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() {
+        return None;
+    }
+
+    let description = description.trim();
+    Some(DataEntry {
+        id: Uuid::new_v4(),
+        key: key.into(),
+        description: if description.is_empty() {
+            Vec::new()
+        } else {
+            vec![DescriptionElement::Text(description.into())]
+        },
+        created: 0,
+        modified: 0,
+        history: Vec::new(),
+        tags: Vec::new(),
+        folder: String::new(),
+        fields: Vec::new(),
+    })
+}
+
+/// Version 2 lines and later, `:` is escaped as `\:` and descriptions contain element tags.
+fn parse_tagged_line(line: &str) -> Option<DataEntry> {
+    static TAGGED_LINE: OnceLock<Regex> = OnceLock::new();
+    let reg =
+        TAGGED_LINE.get_or_init(|| Regex::new(r"(?P<key>(?:\\:|[^:])+):(?P<desc>.*)").unwrap());
+
+    let caps = reg.captures(line)?;
+    let key = SecretString::from(caps["key"].replace(r"\:", ":"));
+    let desc = SecretString::from(caps["desc"].replace(r"\:", ":"));
+
+    Some(DataEntry {
+        created: 0,
+        modified: 0,
+        ..DataEntry::new(key.trim(), desc.trim())
+    })
+}
+
+/// Version 4 lines and later, the tagged line of version 2 prefixed with the permanent id of the
+/// entry. Since version 5 the id is followed by attributes like `created="{unix seconds}"`, since
+/// version 7 by `tags="{comma separated}"` and since version 8 by `folder="{path}"` as well.
+fn parse_identified_line(line: &str) -> Option<DataEntry> {
+    let (header, line) = split_header(line)?;
+    let (id, attributes) = header.split_once(' ').unwrap_or((&header, ""));
+    let attributes = vault::parse_attributes(attributes);
+
+    Some(DataEntry {
+        id: Uuid::parse_str(id.trim()).ok()?,
+        created: timestamp(&attributes, "created")?,
+        modified: timestamp(&attributes, "modified")?,
+        tags: attributes
+            .get("tags")
+            .map(|tags| split_tags(&unescape_attribute(tags)))
+            .unwrap_or_default(),
+        folder: attributes
+            .get("folder")
+            .map(|folder| normalize_folder(&unescape_attribute(folder)))
+            .unwrap_or_default(),
+        ..parse_tagged_line(line)?
+    })
+}
+
+/// Version 6 lines that start with `revision`, an earlier version of the entry above them.
+fn parse_revision_line(line: &str) -> Option<Revision> {
+    let (header, line) = split_header(line)?;
+    let attributes = vault::parse_attributes(header.strip_prefix("revision ")?);
+    let entry = parse_tagged_line(line)?;
+
+    Some(Revision {
+        modified: timestamp(&attributes, "modified")?,
+        key: entry.key.clone(),
+        description: entry.description.clone(),
+    })
+}
+
+/// Version 9 lines that start with `field`, a custom field of the entry above them written as
+/// `field type="{kind}" name="{name}":value`.
+fn parse_field_line(line: &str) -> Option<CustomField> {
+    let (header, value) = split_header(line)?;
+    let attributes = vault::parse_attributes(header.strip_prefix("field ")?);
+
+    Some(CustomField {
+        name: unescape_attribute(attributes.get("name")?),
+        kind: FieldKind::from_name(attributes.get("type")?)?,
+        value: value.replace(r"\:", ":").into(),
+    })
+}
+
+/// Splits the header before the first unescaped `:` from the `key:desc` line after it.
+fn split_header(line: &str) -> Option<(String, &str)> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    let reg = HEADER.get_or_init(|| Regex::new(r"^(?:\\:|[^:])+:").unwrap());
+
+    let header = reg.find(line)?;
+    Some((
+        line[..header.end() - 1].replace(r"\:", ":"),
+        &line[header.end()..],
+    ))
+}
+
+/// A missing timestamp is unknown, an unreadable one makes the line malformed.
+fn timestamp(attributes: &HashMap<String, String>, name: &str) -> Option<u64> {
+    match attributes.get(name) {
+        Some(value) => value.parse().ok(),
+        None => Some(0),
+    }
+}
+
+/// Describes what changes when entries read from `from_version` are saved in the current one.
+fn migrate(entries: &[DataEntry], from_version: u32) -> Option<MigrationReport> {
+    if from_version >= FILE_VERSION {
+        return None;
+    }
+
+    let mut changes = Vec::new();
+    for version in from_version..FILE_VERSION {
+        match version {
+            1 => {
+                changes.push(format!(
+                    "{} entries were read as plain text.",
+                    entries.len()
+                ));
+                for entry in entries {
+                    let text = plain_description(&entry.description);
+                    if parse_description_elements(&text)
+                        .iter()
+                        .any(|element| !matches!(element, DescriptionElement::Text(_)))
+                    {
+                        changes.push(format!(
+                            "\"{}\" has text that will be read as a link, image, sound, secret or TOTP tag.",
+                            entry.key.as_str()
+                        ));
+                    }
+                }
+                let with_colons = entries
+                    .iter()
+                    .filter(|entry| plain_description(&entry.description).contains(':'))
+                    .count();
+                if with_colons > 0 {
+                    changes.push(format!(
+                        "Colons in {} descriptions are now written as \"\\:\".",
+                        with_colons
+                    ));
                 }
-                file.flush()?;
             }
-            Err(e) => return Err(e),
-        };
+            2 => changes
+                .push("The file is now encrypted, a password is set when it is saved.".to_string()),
+            3 => changes.push(format!(
+                "{} entries get a permanent id that is kept in the file.",
+                entries.len()
+            )),
+            4 => changes.push(
+                "Entries now keep when they were created and last modified, the times of existing entries are unknown.".to_string(),
+            ),
+            5 => changes.push(format!(
+                "Up to {} earlier versions of every entry are kept when it is edited.",
+                HISTORY_LIMIT
+            )),
+            6 => changes.push("Entries can now be tagged.".to_string()),
+            7 => changes.push("Entries can now be sorted into folders.".to_string()),
+            8 => changes.push("Entries can now have custom typed fields.".to_string()),
+            _ => {}
+        }
+    }
+
+    Some(MigrationReport {
+        from_version,
+        to_version: FILE_VERSION,
+        changes,
+    })
+}
+
+fn escaped_len(value: &str) -> usize {
+    value.len() + value.matches(':').count()
+}
+
+/// Escapes `:` so it is not taken for the separator between key and description.
+fn push_escaped(line: &mut String, value: &str) {
+    for c in value.chars() {
+        if c == ':' {
+            line.push('\\');
+        }
+        line.push(c);
+    }
+}
 
+/// Writes to a temporary sibling first and renames it over `path`, so the original is never
+/// left half written.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| -> io::Result<()> {
+        use std::io::Write;
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)?;
+
+        // The rename itself only survives a crash once the directory is flushed too.
+        #[cfg(unix)]
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            File::open(parent)?.sync_all()?;
+        }
         Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// A previous version of a file, saved next to it as `{file name}.{timestamp}.bak`. Backups made
+/// within the same second get a number after the timestamp, `{file name}.{timestamp}.{n}.bak`.
+#[derive(Clone, Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Time of the save that replaced this version, `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub saved_at: String,
+    pub size: u64,
+    /// Orders backups with the same `saved_at`, the first one has 0.
+    sequence: u32,
+}
+
+/// Backups of `path`, newest first.
+pub fn list_backups(path: &Path) -> io::Result<Vec<Backup>> {
+    let prefix = format!("{}.", file_name(path)?);
+    let suffix = format!(".{}", BACKUP_EXTENSION);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut backups = Vec::new();
+    for dir_entry in std::fs::read_dir(directory)? {
+        let dir_entry = dir_entry?;
+        let name = dir_entry.file_name().to_string_lossy().to_string();
+        let Some(stamp) = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(&suffix))
+        else {
+            continue;
+        };
+        let (stamp, sequence) = match stamp.split_once('.') {
+            Some((stamp, sequence)) => (stamp, sequence.parse().ok()),
+            None => (stamp, Some(0)),
+        };
+        let (Some(saved_at), Some(sequence)) = (parse_file_timestamp(stamp), sequence) else {
+            continue;
+        };
+
+        backups.push(Backup {
+            path: dir_entry.path(),
+            saved_at,
+            size: dir_entry.metadata()?.len(),
+            sequence,
+        });
+    }
+
+    // The timestamps sort the same way as text.
+    backups.sort_by(|a, b| {
+        b.saved_at
+            .cmp(&a.saved_at)
+            .then(b.sequence.cmp(&a.sequence))
+    });
+    Ok(backups)
+}
+
+/// Copies the current contents of `path` to a new backup and removes all but the newest `keep`.
+fn backup_file(path: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 || !path.exists() {
+        return Ok(());
+    }
+
+    // An autosave and a save can happen within the same second, neither may replace the other.
+    let stamp = format!("{}.{}", file_name(path)?, file_timestamp(unix_time()));
+    let mut backup_path = path.with_file_name(format!("{}.{}", stamp, BACKUP_EXTENSION));
+    let mut sequence = 0;
+    while backup_path.exists() {
+        sequence += 1;
+        backup_path = path.with_file_name(format!("{}.{}.{}", stamp, sequence, BACKUP_EXTENSION));
+    }
+    // Copied as bytes, a file that is not valid text must not stop the save that replaces it.
+    write_atomic(&backup_path, &std::fs::read(path)?)?;
+
+    for old_backup in list_backups(path)?.into_iter().skip(keep) {
+        std::fs::remove_file(old_backup.path)?;
     }
+    Ok(())
+}
+
+/// Puts `backup` back in place of `path`, the version it replaces is backed up first.
+pub fn restore_backup(path: &Path, backup: &Path, keep: usize) -> io::Result<()> {
+    let contents = std::fs::read(backup)?;
+    backup_file(path, keep.max(1))?;
+    write_atomic(path, &contents)
+}
+
+/// Autosave journal of `path`, kept next to it as `{file name}.journal`.
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", JOURNAL_EXTENSION));
+    path.with_file_name(name)
+}
+
+/// True when the journal of `path` was written after the file was last saved.
+pub fn has_newer_journal(path: &Path) -> bool {
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    match (modified(&journal_path(path)), modified(path)) {
+        (Some(journal), Some(file)) => journal > file,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+pub fn remove_journal(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(journal_path(path)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// The JSON form of a vault, meant for scripts, so descriptions are element lists rather than
+/// the markup they are stored as.
+#[derive(Serialize, Deserialize)]
+struct JsonVault {
+    version: u32,
+    entries: Vec<JsonEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEntry {
+    id: Uuid,
+    key: SecretString,
+    description: Vec<JsonElement>,
+    #[serde(default)]
+    created: u64,
+    #[serde(default)]
+    modified: u64,
+    #[serde(default)]
+    history: Vec<JsonRevision>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    folder: String,
+    #[serde(default)]
+    fields: Vec<JsonField>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonField {
+    name: String,
+    kind: FieldKind,
+    value: SecretString,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonRevision {
+    key: SecretString,
+    description: Vec<JsonElement>,
+    modified: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonElement {
+    Text {
+        text: SecretString,
+    },
+    Link {
+        text: String,
+        link: String,
+    },
+    Image {
+        text: String,
+        image: String,
+    },
+    Sound {
+        text: String,
+        sound: String,
+    },
+    Secret {
+        text: String,
+        secret: SecretString,
+    },
+    Totp {
+        text: String,
+        secret: SecretString,
+        digits: u32,
+        period: u64,
+        algorithm: String,
+    },
+}
+
+impl From<&DescriptionElement> for JsonElement {
+    fn from(element: &DescriptionElement) -> Self {
+        match element {
+            DescriptionElement::Text(text) => JsonElement::Text { text: text.clone() },
+            DescriptionElement::Link(ld) => JsonElement::Link {
+                text: ld.text.clone(),
+                link: ld.link.clone(),
+            },
+            DescriptionElement::Image(image) => JsonElement::Image {
+                text: image.text.clone(),
+                image: image.image.clone(),
+            },
+            DescriptionElement::Sound(sound) => JsonElement::Sound {
+                text: sound.text.clone(),
+                sound: sound.sound.clone(),
+            },
+            DescriptionElement::Secret(secret) => JsonElement::Secret {
+                text: secret.text.clone(),
+                secret: secret.secret.clone(),
+            },
+            DescriptionElement::Totp(totp) => JsonElement::Totp {
+                text: totp.text.clone(),
+                secret: totp.totp.secret.clone(),
+                digits: totp.totp.digits,
+                period: totp.totp.period,
+                algorithm: totp.totp.algorithm.to_string(),
+            },
+        }
+    }
+}
+
+impl JsonElement {
+    /// Line breaks in the values become spaces, the file has one entry per line.
+    fn into_element(self) -> io::Result<DescriptionElement> {
+        Ok(match self {
+            JsonElement::Text { text } => DescriptionElement::Text(single_line(&text)),
+            JsonElement::Link { text, link } => DescriptionElement::Link(Link {
+                text: single_line(&text).to_string(),
+                link: single_line(&link).to_string(),
+            }),
+            JsonElement::Image { text, image } => DescriptionElement::Image(DescriptionImage {
+                text: single_line(&text).to_string(),
+                image: single_line(&image).to_string(),
+            }),
+            JsonElement::Sound { text, sound } => DescriptionElement::Sound(DescriptionSound {
+                text: single_line(&text).to_string(),
+                sound: single_line(&sound).to_string(),
+            }),
+            JsonElement::Secret { text, secret } => DescriptionElement::Secret(DescriptionSecret {
+                text: single_line(&text).to_string(),
+                secret: single_line(&secret),
+            }),
+            JsonElement::Totp {
+                text,
+                secret,
+                digits,
+                period,
+                algorithm,
+            } => DescriptionElement::Totp(DescriptionTotp {
+                text: single_line(&text).to_string(),
+                totp: Totp {
+                    secret: single_line(&secret),
+                    digits,
+                    period,
+                    algorithm: TotpAlgorithm::from_name(&algorithm).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Unknown TOTP algorithm {}", algorithm),
+                        )
+                    })?,
+                },
+            }),
+        })
+    }
+}
+
+/// Entries as pretty printed JSON. The result holds every secret in plain text.
+pub fn entries_to_json(entries: &[DataEntry]) -> io::Result<SecretString> {
+    let vault = JsonVault {
+        version: JSON_VERSION,
+        entries: entries
+            .iter()
+            .map(|entry| JsonEntry {
+                id: entry.id,
+                key: entry.key.clone(),
+                description: entry.description.iter().map(JsonElement::from).collect(),
+                created: entry.created,
+                modified: entry.modified,
+                history: entry
+                    .history
+                    .iter()
+                    .map(|revision| JsonRevision {
+                        key: revision.key.clone(),
+                        description: revision.description.iter().map(JsonElement::from).collect(),
+                        modified: revision.modified,
+                    })
+                    .collect(),
+                tags: entry.tags.clone(),
+                folder: entry.folder.clone(),
+                fields: entry
+                    .fields
+                    .iter()
+                    .map(|field| JsonField {
+                        name: field.name.clone(),
+                        kind: field.kind,
+                        value: field.value.clone(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&vault)
+        .map(SecretString::from)
+        .map_err(io::Error::other)
+}
+
+pub fn entries_from_json(json: &str) -> io::Result<Vec<DataEntry>> {
+    let vault: JsonVault =
+        serde_json::from_str(json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if vault.version > JSON_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Version mismatch",
+        ));
+    }
+
+    vault
+        .entries
+        .into_iter()
+        .map(|entry| {
+            Ok(DataEntry {
+                id: entry.id,
+                key: json_key(&entry.key)?,
+                description: entry
+                    .description
+                    .into_iter()
+                    .map(JsonElement::into_element)
+                    .collect::<io::Result<_>>()?,
+                created: entry.created,
+                modified: entry.modified,
+                history: entry
+                    .history
+                    .into_iter()
+                    .map(|revision| {
+                        Ok(Revision {
+                            key: json_key(&revision.key)?,
+                            description: revision
+                                .description
+                                .into_iter()
+                                .map(JsonElement::into_element)
+                                .collect::<io::Result<_>>()?,
+                            modified: revision.modified,
+                        })
+                    })
+                    .collect::<io::Result<_>>()?,
+                tags: split_tags(&single_line(&entry.tags.join(","))),
+                folder: normalize_folder(&single_line(&entry.folder)),
+                fields: entry
+                    .fields
+                    .into_iter()
+                    .map(|field| CustomField {
+                        name: single_line(&field.name).to_string(),
+                        kind: field.kind,
+                        value: single_line(&field.value),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Keys are stored on one line and an entry without one could not be read back.
+fn json_key(key: &str) -> io::Result<SecretString> {
+    let key = single_line(key);
+    if key.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Entry without a key",
+        ));
+    }
+    Ok(key.trim().into())
+}
+
+fn file_name(path: &Path) -> io::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Splits unix seconds into UTC `(year, month, day, hour, minute, second)`.
+fn civil_time(unix_time: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (unix_time / 86_400) as i64;
+    let seconds = (unix_time % 86_400) as u32;
+
+    // Days to a proleptic Gregorian date, from Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_timestamp(unix_time: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil_time(unix_time);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Like `format_timestamp` but usable in a file name.
+fn file_timestamp(unix_time: u64) -> String {
+    format_timestamp(unix_time)
+        .replace(' ', "_")
+        .replace(':', "-")
+}
+
+fn parse_file_timestamp(stamp: &str) -> Option<String> {
+    let (date, time) = stamp.split_once('_')?;
+    let is_valid = date.len() == 10
+        && time.len() == 8
+        && date
+            .chars()
+            .chain(time.chars())
+            .all(|c| c.is_ascii_digit() || c == '-');
+    is_valid.then(|| format!("{} {}", date, time.replace('-', ":")))
 }
 
 pub fn load_icon() -> ImageResult<Icon> {
@@ -174,3 +1493,370 @@ pub fn shift_hue(color: Color, amount_degrees: f32) -> Color {
         a: color.a,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `entries` saved as a file body and read back.
+    fn reload(entries: Vec<DataEntry>) -> Vec<DataEntry> {
+        let mut data = AppData::new(());
+        data.entries = entries;
+        let body = data.serialize_entries();
+        let format = file_format(FILE_VERSION).unwrap();
+        parse_entries(body.lines().map(Ok), 1, format).unwrap()
+    }
+
+    #[test]
+    fn ids_are_kept_in_the_file_and_given_to_legacy_entries() {
+        let mut data = AppData::new(());
+        data.entries = vec![
+            DataEntry::new("Mail", "user"),
+            DataEntry::new("Router", "admin\\: 1"),
+        ];
+
+        let body = data.serialize_entries();
+        let format = file_format(FILE_VERSION).unwrap();
+        let reloaded = parse_entries(body.lines().map(Ok), 1, format).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        for (original, reloaded) in data.entries.iter().zip(&reloaded) {
+            assert_eq!(reloaded.id, original.id);
+            assert_eq!(reloaded.to_line(), original.to_line());
+        }
+
+        // The same line twice, for example pasted into a decrypted body, gets a second id.
+
+        let first = body.lines().next().unwrap();
+        let copied = parse_entries([first, first].into_iter().map(Ok), 1, format).unwrap();
+        assert_eq!(copied[0].id, data.entries[0].id);
+        assert_ne!(copied[1].id, copied[0].id);
+
+        let legacy = parse_entries(
+            ["Mail:user"].into_iter().map(Ok),
+            1,
+            file_format(3).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(legacy[0].key.as_str(), "Mail");
+        assert!(parse_entries(["Mail:user"].into_iter().map(Ok), 1, format).is_err());
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let mut entry = DataEntry::new("Mail", "user");
+        entry.created = 1_700_000_000;
+        entry.modified = 1_700_000_060;
+
+        let reloaded = reload(vec![entry]);
+        assert_eq!(reloaded[0].created, 1_700_000_000);
+        assert_eq!(reloaded[0].modified, 1_700_000_060);
+
+        // Entries of files without timestamps were created at an unknown time.
+        let legacy = parse_entries(
+            ["Mail:user"].into_iter().map(Ok),
+            1,
+            file_format(3).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(legacy[0].created, 0);
+        assert_eq!(legacy[0].modified, 0);
+    }
+
+    #[test]
+    fn history_round_trip() {
+        let mut entry = DataEntry::new("Router", "admin\\: 1");
+        entry.update("Gateway".into(), parse_description_elements("admin: 2"));
+        let revision_modified = entry.history[0].modified;
+
+        let reloaded = reload(vec![entry]);
+        assert_eq!(reloaded[0].history.len(), 1);
+        assert_eq!(reloaded[0].history[0].key.as_str(), "Router");
+        assert_eq!(reloaded[0].history[0].modified, revision_modified);
+        assert_eq!(
+            plain_description(&reloaded[0].history[0].description),
+            "admin\\: 1"
+        );
+        assert_eq!(reloaded[0].key.as_str(), "Gateway");
+    }
+
+    #[test]
+    fn tags_and_folder_round_trip() {
+        let mut entry = DataEntry::new("Router", "admin");
+        entry.folder = normalize_folder("House/\"Garage\": 2");
+        entry.tags = split_tags(r#"home, "rack: 2", home,, a&b"#);
+        assert_eq!(entry.tags, ["home", "\"rack: 2\"", "a&b"]);
+
+        let reloaded = reload(vec![entry.clone(), DataEntry::new("Mail", "user")]);
+        assert_eq!(reloaded[0].tags, entry.tags);
+        assert_eq!(reloaded[0].folder, entry.folder);
+        assert!(reloaded[1].tags.is_empty());
+        assert!(reloaded[1].folder.is_empty());
+    }
+
+    #[test]
+    fn custom_fields_round_trip() {
+        let mut entry = DataEntry::new("Alarm", "front door");
+        entry.fields = vec![
+            CustomField {
+                name: "Alarm \"code\": front".to_string(),
+                kind: FieldKind::Secret,
+                value: "12:34\\".into(),
+            },
+            CustomField {
+                name: "Manual".to_string(),
+                kind: FieldKind::Url,
+                value: "https://example.com/a?b=c".into(),
+            },
+        ];
+        entry.update("Alarm".into(), parse_description_elements("back door"));
+
+        let reloaded = reload(vec![entry.clone()]);
+        assert_eq!(reloaded[0].fields, entry.fields);
+        // Field lines come before the revisions, which still belong to the entry.
+        assert_eq!(reloaded[0].history.len(), 1);
+        assert_eq!(reloaded[0].to_line(), entry.to_line());
+    }
+
+    #[test]
+    fn backups_within_one_second_are_all_kept() {
+        let directory = std::env::temp_dir().join(format!("kriptografija-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("data.txt");
+
+        for version in 0..4 {
+            std::fs::write(&path, format!("version {}", version)).unwrap();
+            backup_file(&path, 3).unwrap();
+        }
+        let backups = list_backups(&path).unwrap();
+        let contents: Vec<String> = backups
+            .iter()
+            .map(|backup| std::fs::read_to_string(&backup.path).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // Newest first, the oldest one is removed because only 3 are kept.
+        assert_eq!(backups.len(), 3);
+        assert_eq!(contents[0], "version 3");
+        assert_eq!(contents[1], "version 2");
+        assert_eq!(contents[2], "version 1");
+    }
+
+    #[test]
+    fn files_that_are_not_text_are_backed_up_and_restored() {
+        let directory = std::env::temp_dir().join(format!("kriptografija-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("data.txt");
+        let bytes = [0xff, 0xfe, b'\n', 0x80];
+
+        std::fs::write(&path, bytes).unwrap();
+        backup_file(&path, 2).unwrap();
+        let backup = list_backups(&path).unwrap().remove(0);
+        std::fs::write(&path, "9").unwrap();
+        restore_backup(&path, &backup.path, 2).unwrap();
+        let restored = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(restored, bytes);
+    }
+
+    #[test]
+    fn opening_a_locked_file_forgets_the_previous_one() {
+        let directory = std::env::temp_dir().join(format!("kriptografija-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("data.txt");
+        let credentials = Credentials {
+            factors: UnlockFactors::Password,
+            password: "password".into(),
+            key_file: None,
+        };
+
+        let mut data = AppData::new(());
+        data.entries = vec![DataEntry::new("Mail", "user")];
+        data.set_password(&credentials).unwrap();
+        data.save_file(path.to_string_lossy().to_string()).unwrap();
+
+        let status = data.load_file(path.to_string_lossy().to_string());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let Ok(LoadStatus::Locked(vault)) = status else {
+            panic!("an encrypted file is returned locked");
+        };
+        assert!(data.entries.is_empty());
+        assert!(!data.has_password());
+
+        data.unlock(&vault, &credentials).unwrap();
+        assert_eq!(data.entries[0].key.as_str(), "Mail");
+    }
+
+    #[test]
+    fn modified_time_only_changes_with_the_entry() {
+        let mut entry = DataEntry::new("Mail", "user");
+        entry.created = 1;
+        entry.modified = 1;
+
+        entry.update("Mail".into(), parse_description_elements("user"));
+        assert_eq!(entry.modified, 1);
+
+        entry.update("Mail".into(), parse_description_elements("other user"));
+        assert!(entry.modified > 1);
+        assert_eq!(entry.created, 1);
+        assert_eq!(entry.history.len(), 1);
+        assert_eq!(entry.history[0].modified, 1);
+    }
+
+    #[test]
+    fn folders_are_listed_with_their_parents() {
+        let mut data = AppData::new(());
+        for (key, folder) in [
+            ("a", "Office/Rack 2"),
+            ("b", " House// Garage /"),
+            ("c", "House"),
+            ("d", ""),
+            ("e", "House B"),
+        ] {
+            let mut entry = DataEntry::new(key, "x");
+            entry.set_folder(folder);
+            data.entries.push(entry);
+        }
+
+        assert_eq!(data.entries[1].folder, "House/Garage");
+        assert!(data.entries[1].is_in_folder("House"));
+        assert!(!data.entries[4].is_in_folder("House"));
+        assert_eq!(
+            data.folders(),
+            [
+                ("House".to_string(), 2),
+                ("House/Garage".to_string(), 1),
+                ("House B".to_string(), 1),
+                ("Office".to_string(), 1),
+                ("Office/Rack 2".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn history_is_bounded_and_reverts_are_kept() {
+        let mut entry = DataEntry::new("Key", "version 0");
+        for version in 1..=HISTORY_LIMIT + 2 {
+            entry.update(
+                "Key".into(),
+                parse_description_elements(&format!("version {}", version)),
+            );
+        }
+        assert_eq!(entry.history.len(), HISTORY_LIMIT);
+        assert_eq!(
+            plain_description(&entry.history[0].description),
+            "version 2"
+        );
+
+        assert!(entry.revert(0));
+        assert_eq!(plain_description(&entry.description), "version 2");
+        assert_eq!(
+            plain_description(&entry.history.last().unwrap().description),
+            format!("version {}", HISTORY_LIMIT + 2)
+        );
+        assert!(!entry.revert(HISTORY_LIMIT));
+    }
+
+    #[test]
+    fn share_text_keeps_custom_fields() {
+        let mut entry = DataEntry::new("Alarm", "front door");
+        entry.fields = vec![CustomField {
+            name: "Code: night".to_string(),
+            kind: FieldKind::Secret,
+            value: "12:34".into(),
+        }];
+
+        let recovered = DataEntry::from_share_text(&entry.to_share_text()).unwrap();
+        assert_eq!(recovered.to_line(), entry.to_line());
+        assert_eq!(recovered.fields, entry.fields);
+
+        // Shares made before custom fields hold only the entry line.
+        let legacy = DataEntry::from_share_text("Alarm:front door").unwrap();
+        assert!(legacy.fields.is_empty());
+        assert!(DataEntry::from_share_text("Alarm:front door\nnot a field").is_err());
+    }
+
+    #[test]
+    fn json_round_trip_matches_the_text_format() {
+        let lines = [
+            r#"Router:admin\: <secret="hunter2" text="Password"> at <link="https://192.168.0.1" text="panel">"#,
+            r#"Mail:<secret="p@ss" text="Password"> <totp="JBSWY3DPEHPK3PXP" text="Code" digits="8" period="60" algorithm="SHA256">"#,
+            r#"Photos:<image="/tmp/cat.png" text="cat"> and <sound="/tmp/meow.mp3" text="meow">"#,
+            "Plain:just text",
+        ];
+        let entries: Vec<DataEntry> = lines
+            .iter()
+            .map(|line| DataEntry::from_line(line).unwrap())
+            .collect();
+
+        let json = entries_to_json(&entries).unwrap();
+        let imported = entries_from_json(&json).unwrap();
+
+        assert_eq!(imported.len(), entries.len());
+        for (original, imported) in entries.iter().zip(&imported) {
+            assert_eq!(imported.id, original.id);
+            assert_eq!(imported.to_line(), original.to_line());
+        }
+    }
+
+    #[test]
+    fn json_descriptions_are_structured() {
+        let entry = DataEntry::new(
+            "Mail",
+            r#"user <secret="p@ss" text="Password"> <totp="JBSWY3DPEHPK3PXP" text="Code">"#,
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&entries_to_json(&[entry]).unwrap()).unwrap();
+
+        let description = &json["entries"][0]["description"];
+        assert_eq!(description[0]["type"], "text");
+        assert_eq!(description[0]["text"], "user ");
+        assert_eq!(description[1]["type"], "secret");
+        assert_eq!(description[1]["secret"], "p@ss");
+        assert_eq!(description[3]["type"], "totp");
+        assert_eq!(description[3]["digits"], 6);
+        assert_eq!(description[3]["algorithm"], "SHA1");
+    }
+
+    #[test]
+    fn json_line_breaks_are_kept_out_of_the_file() {
+        let json = r#"{"version": 1, "entries": [{
+            "id": "6f0c8a39-8c55-4c8e-9a36-1b7f43e3a6a1",
+            "key": "Router\nadmin",
+            "description": [
+                {"type": "text", "text": "first\r\nsecond "},
+                {"type": "secret", "text": "Pass\nword", "secret": "a\nb"}
+            ],
+            "history": [{"key": "Old\nrouter", "description": [], "modified": 1}],
+            "tags": ["home\nrack"],
+            "folder": "House\n/Garage"
+        }]}"#;
+        let reloaded = reload(entries_from_json(json).unwrap());
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].key.as_str(), "Router admin");
+        assert_eq!(
+            plain_description(&reloaded[0].description),
+            "first second Pass word"
+        );
+        assert_eq!(reloaded[0].history[0].key.as_str(), "Old router");
+        assert_eq!(reloaded[0].tags, ["home rack"]);
+        assert_eq!(reloaded[0].folder, "House/Garage");
+    }
+
+    #[test]
+    fn json_entries_without_a_key_are_rejected() {
+        let json = r#"{"version": 1, "entries": [{
+            "id": "6f0c8a39-8c55-4c8e-9a36-1b7f43e3a6a1",
+            "key": " \n ",
+            "description": []
+        }]}"#;
+        let error = entries_from_json(json).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn json_from_a_newer_version_is_rejected() {
+        assert!(entries_from_json(r#"{"version": 99, "entries": []}"#).is_err());
+        assert!(entries_from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secret_string::SecretString;
+
+pub const KEY_LENGTH: usize = 32;
+pub const SALT_LENGTH: usize = 16;
+pub const NONCE_LENGTH: usize = 24;
+pub const KEY_FILE_DIGEST_LENGTH: usize = 32;
+const KEY_FILE_LENGTH: usize = 64;
+
+const KDF_NAME: &str = "argon2id";
+const CIPHER_NAME: &str = "xchacha20poly1305";
+
+/// Bounds of the key derivation cost, the header is read before the file is authenticated and a
+/// crafted one could otherwise make unlocking allocate gigabytes or run for hours.
+pub const MIN_MEMORY_MIB: u32 = 8;
+pub const MAX_MEMORY_MIB: u32 = 1024;
+pub const MAX_ITERATIONS: u32 = 32;
+const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    pub fn is_within_bounds(&self) -> bool {
+        (MIN_MEMORY_MIB * 1024..=MAX_MEMORY_MIB * 1024).contains(&self.memory_kib)
+            && (1..=MAX_ITERATIONS).contains(&self.iterations)
+            && (1..=MAX_PARALLELISM).contains(&self.parallelism)
+    }
+}
+
+/// Which secrets are needed to unlock a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnlockFactors {
+    Password,
+    KeyFile,
+    PasswordAndKeyFile,
+}
+
+impl UnlockFactors {
+    pub const ALL: [UnlockFactors; 3] = [
+        UnlockFactors::Password,
+        UnlockFactors::KeyFile,
+        UnlockFactors::PasswordAndKeyFile,
+    ];
+
+    pub fn needs_password(self) -> bool {
+        self != UnlockFactors::KeyFile
+    }
+
+    pub fn needs_key_file(self) -> bool {
+        self != UnlockFactors::Password
+    }
+
+    fn as_attribute(self) -> &'static str {
+        match self {
+            UnlockFactors::Password => "password",
+            UnlockFactors::KeyFile => "keyfile",
+            UnlockFactors::PasswordAndKeyFile => "password+keyfile",
+        }
+    }
+
+    fn from_attribute(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|factors| factors.as_attribute() == value)
+    }
+}
+
+impl fmt::Display for UnlockFactors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnlockFactors::Password => write!(f, "Password"),
+            UnlockFactors::KeyFile => write!(f, "Key file"),
+            UnlockFactors::PasswordAndKeyFile => write!(f, "Password + key file"),
+        }
+    }
+}
+
+/// The secrets entered by the user, the key file is kept only as its digest.
+#[derive(Clone)]
+pub struct Credentials {
+    pub factors: UnlockFactors,
+    pub password: SecretString,
+    pub key_file: Option<[u8; KEY_FILE_DIGEST_LENGTH]>,
+}
+
+impl Credentials {
+    /// Combines the required factors into the input of the key derivation. A password alone is
+    /// used as is, so files from before key files existed still open.
+    fn secret(&self) -> io::Result<Zeroizing<Vec<u8>>> {
+        let mut secret = Zeroizing::new(Vec::with_capacity(
+            self.password.len() + KEY_FILE_DIGEST_LENGTH,
+        ));
+
+        if self.factors.needs_password() {
+            secret.extend_from_slice(self.password.as_bytes());
+        }
+        if self.factors.needs_key_file() {
+            let digest = self.key_file.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "A key file is required")
+            })?;
+            secret.extend_from_slice(&digest);
+        }
+
+        Ok(secret)
+    }
+}
+
+pub fn read_key_file(path: &Path) -> io::Result<[u8; KEY_FILE_DIGEST_LENGTH]> {
+    let contents = fs::read(path)?;
+
+    if contents.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Key file is empty",
+        ));
+    }
+
+    Ok(Sha256::digest(&contents).into())
+}
+
+pub fn create_key_file(path: &Path) -> io::Result<()> {
+    let mut contents = [0u8; KEY_FILE_LENGTH];
+    OsRng.fill_bytes(&mut contents);
+    fs::write(path, contents)
+}
+
+/// Key derived from the user's credentials, kept in memory so saving does not ask for them again.
+#[derive(Clone)]
+pub struct VaultKey {
+    key: [u8; KEY_LENGTH],
+    key_file: Option<[u8; KEY_FILE_DIGEST_LENGTH]>,
+    pub factors: UnlockFactors,
+    pub kdf: KdfParams,
+    pub salt: [u8; SALT_LENGTH],
+}
+
+impl Drop for VaultKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.key_file.zeroize();
+    }
+}
+
+impl VaultKey {
+    /// Derives a key with a freshly generated salt.
+    pub fn new(credentials: &Credentials, kdf: KdfParams) -> io::Result<Self> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(credentials, kdf, salt)
+    }
+
+    pub fn derive(
+        credentials: &Credentials,
+        kdf: KdfParams,
+        salt: [u8; SALT_LENGTH],
+    ) -> io::Result<Self> {
+        let params = Params::new(
+            kdf.memory_kib,
+            kdf.iterations,
+            kdf.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid key derivation parameters: {}", e),
+            )
+        })?;
+
+        let mut key = [0u8; KEY_LENGTH];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(&credentials.secret()?, &salt, &mut key)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Key derivation failed: {}", e),
+                )
+            })?;
+
+        Ok(Self {
+            key,
+            key_file: credentials.key_file,
+            factors: credentials.factors,
+            kdf,
+            salt,
+        })
+    }
+
+    /// Checks `password` against this key by deriving it again with the same salt, parameters
+    /// and key file.
+    pub fn matches(&self, password: &str) -> io::Result<bool> {
+        let credentials = Credentials {
+            factors: self.factors,
+            password: password.into(),
+            key_file: self.key_file,
+        };
+        let other = Self::derive(&credentials, self.kdf, self.salt)?;
+
+        Ok(self
+            .key
+            .iter()
+            .zip(other.key.iter())
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+            == 0)
+    }
+}
+
+/// Everything stored in the clear in front of the encrypted body.
+#[derive(Clone, Debug)]
+pub struct VaultHeader {
+    pub factors: UnlockFactors,
+    pub kdf: KdfParams,
+    pub salt: [u8; SALT_LENGTH],
+    pub nonce: [u8; NONCE_LENGTH],
+}
+
+impl VaultHeader {
+    pub fn parse(line: &str) -> io::Result<Self> {
+        let attributes = parse_attributes(
+            line.trim()
+                .trim_start_matches("<vault")
+                .trim_end_matches('>'),
+        );
+
+        if attributes.get("kdf").map(String::as_str) != Some(KDF_NAME)
+            || attributes.get("cipher").map(String::as_str) != Some(CIPHER_NAME)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unsupported vault encryption",
+            ));
+        }
+
+        // Files written before key files were supported have no factors attribute.
+        let factors = match attributes.get("factors") {
+            Some(value) => UnlockFactors::from_attribute(value).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Unsupported unlock factors")
+            })?,
+            None => UnlockFactors::Password,
+        };
+
+        let kdf = KdfParams {
+            memory_kib: parse_number(&attributes, "m")?,
+            iterations: parse_number(&attributes, "t")?,
+            parallelism: parse_number(&attributes, "p")?,
+        };
+        if !kdf.is_within_bounds() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Vault key derivation cost is out of range",
+            ));
+        }
+
+        Ok(Self {
+            factors,
+            kdf,
+            salt: decode_fixed(&attributes, "salt")?,
+            nonce: decode_fixed(&attributes, "nonce")?,
+        })
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(
+            "<vault factors=\"{}\" kdf=\"{}\" m=\"{}\" t=\"{}\" p=\"{}\" salt=\"{}\" cipher=\"{}\" nonce=\"{}\">",
+            self.factors.as_attribute(),
+            KDF_NAME,
+            self.kdf.memory_kib,
+            self.kdf.iterations,
+            self.kdf.parallelism,
+            STANDARD.encode(self.salt),
+            CIPHER_NAME,
+            STANDARD.encode(self.nonce)
+        )
+    }
+}
+
+/// An encrypted file that was read from disk but not yet decrypted.
+#[derive(Clone, Debug)]
+pub struct LockedVault {
+    pub version: u32,
+    pub header: VaultHeader,
+    header_line: String,
+    ciphertext: Vec<u8>,
+}
+
+impl LockedVault {
+    pub fn parse(version: u32, header_line: &str, body: &str) -> io::Result<Self> {
+        let ciphertext = STANDARD
+            .decode(body.trim())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Malformed encrypted body"))?;
+
+        Ok(Self {
+            version,
+            header: VaultHeader::parse(header_line)?,
+            header_line: header_line.trim().to_string(),
+            ciphertext,
+        })
+    }
+
+    pub fn header_line(&self) -> &str {
+        &self.header_line
+    }
+
+    pub fn encoded_body(&self) -> String {
+        STANDARD.encode(&self.ciphertext)
+    }
+
+    /// Derives the key from `credentials` and returns it together with the decrypted body.
+    pub fn unlock(&self, credentials: &Credentials) -> io::Result<(VaultKey, SecretString)> {
+        let credentials = Credentials {
+            factors: self.header.factors,
+            ..credentials.clone()
+        };
+        let key = VaultKey::derive(&credentials, self.header.kdf, self.header.salt)?;
+        let cipher = XChaCha20Poly1305::new((&key.key).into());
+
+        let plaintext = cipher
+            .decrypt(
+                XNonce::from_slice(&self.header.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: associated_data(self.version, &self.header_line).as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Wrong password or the file was modified",
+                )
+            })?;
+
+        let body = String::from_utf8(plaintext).map_err(|e| {
+            e.into_bytes().zeroize();
+            io::Error::new(io::ErrorKind::InvalidData, "Decrypted data is not text")
+        })?;
+
+        Ok((key, SecretString::from(body)))
+    }
+}
+
+/// Encrypts `plaintext` with a fresh nonce. The result can be written to disk with
+/// `header_line` and `encoded_body`, or kept in memory while the app is locked.
+pub fn seal(key: &VaultKey, version: u32, plaintext: &str) -> io::Result<LockedVault> {
+    let cipher = XChaCha20Poly1305::new((&key.key).into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let header = VaultHeader {
+        factors: key.factors,
+        kdf: key.kdf,
+        salt: key.salt,
+        nonce: nonce.into(),
+    };
+    let header_line = header.serialize();
+
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: associated_data(version, &header_line).as_bytes(),
+            },
+        )
+        .map_err(|_| io::Error::other("Encryption failed"))?;
+
+    Ok(LockedVault {
+        version,
+        header,
+        header_line,
+        ciphertext,
+    })
+}
+
+/// The version and header are authenticated together with the body so they cannot be swapped.
+fn associated_data(version: u32, header_line: &str) -> String {
+    format!("{}\n{}", version, header_line)
+}
+
+pub fn parse_attributes(source: &str) -> HashMap<String, String> {
+    let re = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+
+    re.captures_iter(source)
+        .map(|cap| (cap[1].to_string(), cap[2].to_string()))
+        .collect()
+}
+
+fn parse_number(attributes: &HashMap<String, String>, name: &str) -> io::Result<u32> {
+    attributes
+        .get(name)
+        .and_then(|value| value.parse::<u32>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Missing or invalid vault parameter '{}'", name),
+            )
+        })
+}
+
+fn decode_fixed<const N: usize>(
+    attributes: &HashMap<String, String>,
+    name: &str,
+) -> io::Result<[u8; N]> {
+    attributes
+        .get(name)
+        .and_then(|value| STANDARD.decode(value).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Missing or invalid vault parameter '{}'", name),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(m: u32, t: u32, p: u32) -> String {
+        format!(
+            "<vault factors=\"password\" kdf=\"argon2id\" m=\"{}\" t=\"{}\" p=\"{}\" salt=\"{}\" cipher=\"xchacha20poly1305\" nonce=\"{}\">",
+            m,
+            t,
+            p,
+            STANDARD.encode([0u8; SALT_LENGTH]),
+            STANDARD.encode([0u8; NONCE_LENGTH])
+        )
+    }
+
+    #[test]
+    fn key_derivation_cost_is_bounded() {
+        let kdf = KdfParams::default();
+        let parsed =
+            VaultHeader::parse(&header(kdf.memory_kib, kdf.iterations, kdf.parallelism)).unwrap();
+        assert_eq!(parsed.kdf, kdf);
+        assert!(VaultHeader::parse(&header(MAX_MEMORY_MIB * 1024, MAX_ITERATIONS, 1)).is_ok());
+
+        for (m, t, p) in [
+            (u32::MAX, 2, 1),
+            (MAX_MEMORY_MIB * 1024 + 1, 2, 1),
+            (kdf.memory_kib, MAX_ITERATIONS + 1, 1),
+            (kdf.memory_kib, 0, 1),
+            (kdf.memory_kib, 2, 0),
+            (kdf.memory_kib, 2, 255),
+            (1, 2, 1),
+        ] {
+            let error = VaultHeader::parse(&header(m, t, p)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -23,6 +23,22 @@ pub enum WindowType {
     Sound,
     Error,
     EntryEditor,
+    Password,
+    Generator,
+    ShareExport,
+    ShareRecovery,
+    Backups,
+    CsvImport,
+    Import,
+    History,
+    MoveToFolder,
+}
+
+#[derive(Debug, Clone)]
+pub enum PasswordPurpose {
+    Unlock,
+    Create(String),
+    Change(String),
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +46,7 @@ pub enum WindowContentType {
     StringContent(String),
     ImageContant(DescriptionImage),
     SoundContent(DescriptionSound),
+    PasswordContent(PasswordPurpose),
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +59,9 @@ pub struct WindowContent<Message> {
     pub show_cancel: bool,
     pub show_okay: bool,
     pub on_okay: Option<Box<Message>>,
+    pub okay_label: String,
+    /// Shows a Discard button next to Okay and Cancel, the message is what discarding leads to.
+    pub on_discard: Option<Box<Message>>,
 }
 
 impl<Message> WindowContent<Message> {
@@ -63,8 +83,20 @@ impl<Message> WindowContent<Message> {
             show_cancel,
             show_okay,
             on_okay: on_okay.map(Box::new),
+            okay_label: "Okay".to_string(),
+            on_discard: None,
         }
     }
+
+    pub fn with_okay_label(mut self, label: &str) -> Self {
+        self.okay_label = label.to_string();
+        self
+    }
+
+    pub fn with_discard(mut self, on_discard: Message) -> Self {
+        self.on_discard = Some(Box::new(on_discard));
+        self
+    }
 }
 
 pub fn custom_window<'a, Message>(
@@ -72,6 +104,7 @@ pub fn custom_window<'a, Message>(
     on_close: Message,
     on_okay: Message,
     on_cancel: Message,
+    on_discard: Option<Message>,
     body: Option<impl Into<Element<'a, Message, Theme, Renderer>>>,
 ) -> Element<'a, Message>
 where
@@ -109,8 +142,16 @@ where
     let mut footer: Row<'_, Message, Theme, Renderer> = row![horizontal()].spacing(10).width(Fill);
 
     if window_content.show_okay {
-        footer = footer.push(custom_button("Okay", on_okay, |theme| {
-            theme.palette().primary
+        footer = footer.push(custom_button(
+            text(window_content.okay_label.clone()),
+            on_okay,
+            |theme| theme.palette().primary,
+        ));
+    }
+
+    if let Some(on_discard) = on_discard {
+        footer = footer.push(custom_button("Discard", on_discard, |theme| {
+            theme.palette().warning
         }));
     }
 
@@ -25,6 +25,10 @@ impl<Message> WindowManager<Message> {
         self.windows.pop()
     }
 
+    pub fn clear(&mut self) {
+        self.windows.clear();
+    }
+
     pub fn remove_window_by_id(&mut self, id: uuid::Uuid) -> Option<WindowContent<Message>> {
         if let Some(pos) = self.windows.iter().position(|x| x.id == id) {
             Some(self.windows.remove(pos))